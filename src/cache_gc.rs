@@ -0,0 +1,229 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::dedupe::get_data_path;
+use crate::header::MailHeader;
+use crate::process::execute::{delete_file, ensure_writable_directory, move_mail};
+use crate::process::source_path::get_cache_root_path;
+use crate::process::target_path::create_new_mail_name;
+use crate::tmp_files::get_age_threshold;
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::collections::HashSet;
+
+#[cfg(test)]
+pub(crate) mod test_cache_gc;
+
+/// Classification of a file below the Akonadi payload cache directory.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheFileKind {
+    /// Payload referenced by a `parttable` row with `storage = 1`
+    Referenced,
+    /// Unreferenced file modified recently, possibly written by a running
+    /// Akonadi before committing its part row or staged by a concurrent run
+    Recent,
+    /// Temporary copy of a database payload left behind by `get_cached_email`
+    StaleTmp,
+    /// Unreferenced payload which looks like a mail
+    UnreferencedMail,
+    /// Unreferenced payload of any other content
+    Unreferenced,
+}
+
+#[derive(Debug)]
+pub struct CacheFile {
+    pub path: String,
+    pub size: u64,
+    pub kind: CacheFileKind,
+}
+
+/// Fetch the names of all payload files referenced from `parttable`.
+async fn fetch_referenced_payloads(pool: Pool<MySql>) -> Result<HashSet<String>> {
+    let rows: Vec<(String,)> =
+        sqlx::query_as("SELECT CONVERT(`data`, CHAR) FROM `parttable` WHERE `storage` = 1")
+            .fetch_all(&pool)
+            .await?;
+    Ok(rows.into_iter().map(|(name,)| name).collect())
+}
+
+/// Check whether a file starts with a mail header block, i.e. an optional
+/// mbox `From ` line followed by header lines, including at least one of
+/// `From`, `Date`, `Message-ID` or `Received`.
+pub fn looks_like_mail(path: &str) -> Result<bool> {
+    Ok(MailHeader::read(path)?.is_mail())
+}
+
+/// Check whether an `akonadiserver` process is running.
+pub fn is_akonadi_running() -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.filter_map(|e| e.ok()).any(|entry| {
+        std::fs::read_to_string(entry.path().join("comm"))
+            .is_ok_and(|comm| comm.trim() == "akonadiserver")
+    })
+}
+
+/// Classify all files below the payload cache directory.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `min_age_hours`: Unreferenced files modified more recently are left alone
+///
+/// Returns `Result<Vec<CacheFile>>`
+///
+pub async fn scan_cache_files(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    min_age_hours: u64,
+) -> Result<Vec<CacheFile>> {
+    let cache_root = get_cache_root_path(args)?;
+    let referenced = fetch_referenced_payloads(pool.clone()).await?;
+    let threshold = get_age_threshold(min_age_hours);
+    let mut files = Vec::new();
+
+    for entry in walkdir::WalkDir::new(&cache_root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path().to_string_lossy().to_string();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let metadata = entry.metadata()?;
+        let kind = if referenced.contains(&name) {
+            CacheFileKind::Referenced
+        } else if metadata.modified()? > threshold {
+            CacheFileKind::Recent
+        } else if name.starts_with("tmp_db_") {
+            CacheFileKind::StaleTmp
+        } else if looks_like_mail(&path)? {
+            CacheFileKind::UnreferencedMail
+        } else {
+            CacheFileKind::Unreferenced
+        };
+        files.push(CacheFile {
+            path,
+            size: metadata.len(),
+            kind,
+        });
+    }
+    Ok(files)
+}
+
+/// Garbage-collect the Akonadi payload cache.
+/// - Unreferenced payloads looking like mails are moved into the `new`
+///   directory of the recovery maildir under a fresh maildir name.
+/// - Other unreferenced payloads and stale `tmp_db_` files are removed.
+/// - Referenced payloads and recently modified files are left alone.
+/// - Unless in dry-run mode, refuse to run while Akonadi is running, as it
+///   writes payload files before committing their part rows.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `recovery_path`: Recovery maildir or "auto"
+/// - `min_age_hours`: Unreferenced files modified more recently are left alone
+///
+/// Returns `Result<()>`
+///
+pub async fn collect_cache_garbage(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    recovery_path: &str,
+    min_age_hours: u64,
+) -> Result<()> {
+    if is_akonadi_running() {
+        if !args.dry_run {
+            anyhow::bail!("Akonadi is running, stop it with `akonadictl stop` before cache-gc");
+        }
+        println!("Akonadi is running, a real run would refuse to collect garbage.");
+    }
+    let dry_run_msg_move = if args.dry_run { "Would move" } else { "Moving" };
+    let dry_run_msg_delete = if args.dry_run {
+        "Would delete"
+    } else {
+        "Deleting"
+    };
+    let recovery = get_data_path(recovery_path, "recovery")?;
+    let files = scan_cache_files(pool.clone(), args, min_age_hours).await?;
+    let mut rescued = 0;
+    let mut removed = 0;
+    let mut reclaimed: u64 = 0;
+
+    for file in &files {
+        match file.kind {
+            CacheFileKind::Referenced | CacheFileKind::Recent => continue,
+            CacheFileKind::UnreferencedMail => {
+                let name = create_new_mail_name(pool.clone(), &file.path, &recovery, None).await?;
+                let target = format!("{}new/{}", recovery, name);
+                if args.verbose || args.dry_run {
+                    println!("{} {} to {}", dry_run_msg_move, file.path, target);
+                }
+                if !args.dry_run {
+                    for sub_dir in ["cur", "tmp"] {
+                        ensure_writable_directory(format!("{}{}", recovery, sub_dir))?;
+                    }
+                    if move_mail(pool.clone(), args, &file.path, &target)
+                        .await?
+                        .is_none()
+                    {
+                        continue;
+                    }
+                }
+                rescued += 1;
+            }
+            CacheFileKind::StaleTmp | CacheFileKind::Unreferenced => {
+                if args.verbose || args.dry_run {
+                    println!("{} {} ({} bytes)", dry_run_msg_delete, file.path, file.size);
+                }
+                if !args.dry_run {
+                    delete_file(&file.path)?;
+                }
+                removed += 1;
+                // Rescued mails keep occupying their space
+                reclaimed += file.size;
+            }
+        }
+    }
+
+    println!(
+        "{} cache files scanned, {} mails {} to {}, {} files {}, {} bytes {}.",
+        files.len(),
+        rescued,
+        if args.dry_run {
+            "would be rescued"
+        } else {
+            "rescued"
+        },
+        recovery,
+        removed,
+        if args.dry_run {
+            "would be removed"
+        } else {
+            "removed"
+        },
+        reclaimed,
+        if args.dry_run {
+            "would be reclaimed"
+        } else {
+            "reclaimed"
+        }
+    );
+    Ok(())
+}
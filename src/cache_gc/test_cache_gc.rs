@@ -0,0 +1,168 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the garbage collection of the Akonadi payload cache.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. Next to the three referenced payloads,
+/// an unreferenced mail payload, an unreferenced binary payload and a stale `tmp_db_`
+/// file are added to `file_db_data` and aged beyond the threshold. A recent
+/// `tmp_db_` file, as staged by a concurrent run, is added as well.
+///
+/// # Test Cases
+///
+/// - `test_looks_like_mail`: Verifies the detection of mail payloads.
+/// - `test_scan_cache_files`: Verifies the classification of the cache files.
+/// - `test_collect_cache_garbage`: Verifies that the mail is rescued, the other
+///   unreferenced files are removed and the referenced payloads and the recent
+///   file are kept.
+/// - `test_collect_cache_garbage_dry_run`: Verifies that nothing is changed in
+///   dry-run mode.
+///
+mod tests {
+    use crate::cache_gc::{
+        collect_cache_garbage, looks_like_mail, scan_cache_files, CacheFileKind,
+    };
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    const REFERENCED: &str = "file_db_data/42/152642_r0";
+    const ORPHAN_MAIL: &str = "file_db_data/42/152699_r0";
+    const ORPHAN_BINARY: &str = "file_db_data/54/152698_r0";
+    const STALE_TMP: &str = "file_db_data/tmp_db_1b4e28ba-2fa1-11d2-883f-0016d3cca427";
+    const RECENT_TMP: &str = "file_db_data/tmp_db_6fa459ea-ee8a-3ca4-894e-db77e160355e";
+    const MIN_AGE_HOURS: u64 = 36;
+
+    /// Helper function adding the unreferenced files to the temporary cache directory.
+    fn add_garbage(temp_dir: &str) -> Result<()> {
+        std::fs::copy(
+            format!("{}/{}", temp_dir, REFERENCED),
+            format!("{}/{}", temp_dir, ORPHAN_MAIL),
+        )?;
+        std::fs::write(
+            format!("{}/{}", temp_dir, ORPHAN_BINARY),
+            [0u8, 159, 146, 150],
+        )?;
+        std::fs::copy(
+            format!("{}/{}", temp_dir, REFERENCED),
+            format!("{}/{}", temp_dir, STALE_TMP),
+        )?;
+        let old = SystemTime::now() - Duration::from_secs(48 * 3600);
+        for file in [ORPHAN_MAIL, ORPHAN_BINARY, STALE_TMP] {
+            std::fs::File::options()
+                .write(true)
+                .open(format!("{}/{}", temp_dir, file))?
+                .set_modified(old)?;
+        }
+        std::fs::copy(
+            format!("{}/{}", temp_dir, REFERENCED),
+            format!("{}/{}", temp_dir, RECENT_TMP),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_looks_like_mail() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join(format!("looks_like_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir)?;
+        let mail = temp_dir.join("mail").to_string_lossy().to_string();
+        let mbox = temp_dir.join("mbox").to_string_lossy().to_string();
+        let other = temp_dir.join("other").to_string_lossy().to_string();
+        std::fs::write(&mail, "Subject: Hi\nFrom: a@b\n\nHello\n")?;
+        std::fs::write(
+            &mbox,
+            "From a@b Mon Jan  1 00:00:00 2024\nDate: x\n\nHello\n",
+        )?;
+        std::fs::write(&other, "BEGIN:VCARD\nVERSION:3.0\nEND:VCARD\n")?;
+
+        assert!(looks_like_mail(&mail)?);
+        assert!(looks_like_mail(&mbox)?);
+        assert!(!looks_like_mail(&other)?);
+
+        std::fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_scan_cache_files(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_garbage(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let files = scan_cache_files(pool.clone(), &args, MIN_AGE_HOURS).await?;
+        assert_eq!(files.len(), 7);
+        let kind_of = |suffix: &str| {
+            files
+                .iter()
+                .find(|f| f.path.ends_with(suffix))
+                .map(|f| f.kind.clone())
+        };
+        assert_eq!(kind_of(REFERENCED), Some(CacheFileKind::Referenced));
+        assert_eq!(kind_of(ORPHAN_MAIL), Some(CacheFileKind::UnreferencedMail));
+        assert_eq!(kind_of(ORPHAN_BINARY), Some(CacheFileKind::Unreferenced));
+        assert_eq!(kind_of(STALE_TMP), Some(CacheFileKind::StaleTmp));
+        assert_eq!(kind_of(RECENT_TMP), Some(CacheFileKind::Recent));
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_collect_cache_garbage(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_garbage(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+        let recovery = format!("{}/recovery/", temp_dir);
+
+        collect_cache_garbage(pool.clone(), &args, &recovery, MIN_AGE_HOURS).await?;
+
+        for kept in [REFERENCED, RECENT_TMP] {
+            assert!(Path::new(&format!("{}/{}", temp_dir, kept)).is_file());
+        }
+        for garbage in [ORPHAN_MAIL, ORPHAN_BINARY, STALE_TMP] {
+            assert!(!Path::new(&format!("{}/{}", temp_dir, garbage)).exists());
+        }
+        let rescued: Vec<_> = std::fs::read_dir(format!("{}new", recovery))?.collect();
+        assert_eq!(rescued.len(), 1);
+        assert!(Path::new(&format!("{}cur", recovery)).is_dir());
+        assert!(Path::new(&format!("{}tmp", recovery)).is_dir());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_collect_cache_garbage_dry_run(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_garbage(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, true);
+        let recovery = format!("{}/recovery/", temp_dir);
+
+        collect_cache_garbage(pool.clone(), &args, &recovery, MIN_AGE_HOURS).await?;
+
+        for file in [REFERENCED, ORPHAN_MAIL, ORPHAN_BINARY, STALE_TMP] {
+            assert!(Path::new(&format!("{}/{}", temp_dir, file)).is_file());
+        }
+        assert!(!Path::new(&recovery).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
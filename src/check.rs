@@ -0,0 +1,315 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::impact::{report_delete_impact, report_delete_totals, DeleteImpact};
+use crate::orphans::akonadi_ignores;
+use crate::process::execute::{move_mail, update_akonadi_db, update_item_in_place};
+use crate::process::maildirs::fetch_full_paths;
+use crate::process::target_path::{build_mail_info, split_mail_name};
+use crate::special::{fetch_special_collections, find_queued_item};
+use crate::todoitems::fetch_items_by_remote_id;
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::path::Path;
+
+#[cfg(test)]
+pub(crate) mod test_check;
+
+/// Flag letters defined by the maildir specification.
+pub const KNOWN_FLAGS: &str = "DFPRST";
+
+/// Entries counted by `check_and_fix_maildirs`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CheckTotals {
+    /// Entries violating the rules
+    pub violating: usize,
+    /// Violating entries a rename would fix
+    pub fixable: usize,
+    /// Entries renamed, or that would be renamed in dry-run mode
+    pub fixed: usize,
+}
+
+/// Violation of the maildir rules by a single directory entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// File in `new` carrying an info suffix
+    InfoInNew,
+    /// File in `cur` without `:2,`
+    NoInfoInCur,
+    /// Info suffix other than `:2,`
+    UnknownInfo,
+    /// Flag letters not in ASCII order
+    UnsortedFlags,
+    /// Flag letter given more than once
+    DuplicateFlags,
+    /// Flag letter neither defined by the maildir specification nor a
+    /// Dovecot keyword letter `a`-`z`
+    UnknownFlags,
+    /// Entry is not a regular file
+    NotRegular,
+    /// Akonadi's maildir resource skips the entry
+    RejectedByAkonadi(&'static str),
+}
+
+impl Violation {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Violation::InfoInNew => "info suffix in new, belongs to cur",
+            Violation::NoInfoInCur => "missing info suffix \":2,\" in cur",
+            Violation::UnknownInfo => "unknown info suffix, expected \":2,\"",
+            Violation::UnsortedFlags => "flags not in ASCII order",
+            Violation::DuplicateFlags => "duplicated flags",
+            Violation::UnknownFlags => "unknown flag letters",
+            Violation::NotRegular => "not a regular file",
+            Violation::RejectedByAkonadi(reason) => reason,
+        }
+    }
+
+    /// Whether renaming the file resolves the violation.
+    pub fn is_fixable(&self) -> bool {
+        matches!(
+            self,
+            Violation::InfoInNew
+                | Violation::NoInfoInCur
+                | Violation::UnsortedFlags
+                | Violation::DuplicateFlags
+        )
+    }
+}
+
+/// All violations of a directory entry in a maildir folder.
+#[derive(Debug)]
+pub struct MailCheck {
+    pub path: String,
+    pub collection_id: i64,
+    pub violations: Vec<Violation>,
+    /// Conforming path the file can be renamed to
+    pub fix: Option<String>,
+}
+
+/// Check the name of a mail file against the maildir rules.
+///
+/// # Arguments
+/// - `sub_dir`: `new` or `cur`
+/// - `name`: File name
+/// - `dovecot_keywords`: Accept Dovecot keyword letters `a` to `z`
+///
+/// Returns the list of violations
+///
+pub fn check_mail_name(sub_dir: &str, name: &str, dovecot_keywords: bool) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let has_info = name.contains(":2,");
+    if name.contains(':') && !has_info {
+        violations.push(Violation::UnknownInfo);
+    }
+    if sub_dir == "new" && has_info {
+        violations.push(Violation::InfoInNew);
+    }
+    if sub_dir == "cur" && !has_info {
+        violations.push(Violation::NoInfoInCur);
+    }
+    let (_, flags) = split_mail_name(name);
+    if flags.windows(2).any(|w| w[0] > w[1]) {
+        violations.push(Violation::UnsortedFlags);
+    }
+    let mut unique_flags = flags.clone();
+    unique_flags.sort();
+    unique_flags.dedup();
+    if unique_flags.len() != flags.len() {
+        violations.push(Violation::DuplicateFlags);
+    }
+    if flags
+        .iter()
+        .any(|f| !(KNOWN_FLAGS.contains(*f) || dovecot_keywords && f.is_ascii_lowercase()))
+    {
+        violations.push(Violation::UnknownFlags);
+    }
+    violations
+}
+
+/// Conforming sub-directory and name of a mail file. Flags are sorted and
+/// deduplicated, mails without flags belong to `new`, all others to `cur`.
+/// Files with an unknown info suffix are left alone.
+///
+/// Returns `None` if the file cannot be fixed by renaming or needs no fix
+///
+pub fn fixed_mail_name(
+    sub_dir: &str,
+    name: &str,
+    dovecot_keywords: bool,
+) -> Option<(&'static str, String)> {
+    let violations = check_mail_name(sub_dir, name, dovecot_keywords);
+    if violations.contains(&Violation::UnknownInfo) || !violations.iter().any(|v| v.is_fixable()) {
+        return None;
+    }
+    let (unique, flags) = split_mail_name(name);
+    let mail_info = build_mail_info(flags);
+    let cur_new_name = if mail_info.is_empty() { "new" } else { "cur" };
+    Some((cur_new_name, format!("{}{}", unique, mail_info)))
+}
+
+/// Walk the `new` and `cur` directories of all local folders and collect
+/// the entries violating the maildir rules.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<Vec<MailCheck>>`
+///
+pub async fn check_maildirs(pool: Pool<MySql>, args: &CliArgs) -> Result<Vec<MailCheck>> {
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let mut collections: Vec<(&i64, &String)> = full_paths.iter().collect();
+    collections.sort_by(|a, b| a.1.cmp(b.1));
+
+    let mut checks = Vec::new();
+    for (collection_id, path) in collections {
+        for sub_dir in ["new", "cur"] {
+            let Ok(entries) = std::fs::read_dir(format!("{}{}", path, sub_dir)) else {
+                continue;
+            };
+            let mut entries: Vec<std::fs::DirEntry> = entries.filter_map(|e| e.ok()).collect();
+            entries.sort_by_key(|e| e.file_name());
+            for entry in entries {
+                let file_name = entry.file_name();
+                let name = file_name.to_string_lossy();
+                let is_file = entry.file_type().is_ok_and(|t| t.is_file());
+                let mut violations = Vec::new();
+                if let Some(reason) = akonadi_ignores(&file_name, true) {
+                    violations.push(Violation::RejectedByAkonadi(reason));
+                }
+                if is_file {
+                    violations.extend(check_mail_name(sub_dir, &name, args.dovecot_keywords));
+                } else {
+                    violations.push(Violation::NotRegular);
+                }
+                if violations.is_empty() {
+                    continue;
+                }
+                let fix = if is_file && file_name.to_str().is_some() {
+                    fixed_mail_name(sub_dir, &name, args.dovecot_keywords)
+                        .map(|(cur_new_name, fixed)| format!("{}{}/{}", path, cur_new_name, fixed))
+                } else {
+                    None
+                };
+                checks.push(MailCheck {
+                    path: entry.path().to_string_lossy().to_string(),
+                    collection_id: *collection_id,
+                    violations,
+                    fix,
+                });
+            }
+        }
+    }
+    Ok(checks)
+}
+
+/// Report all maildir rule violations and optionally fix them by renaming
+/// the files in place. Akonadi items referring to a renamed file are
+/// removed from the database, such that Akonadi re-imports the file.
+/// Files of items queued in the Outbox are left alone.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `fix`: Rename the files violating the rules
+///
+/// Returns `Result<CheckTotals>`
+///
+pub async fn check_and_fix_maildirs(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    fix: bool,
+) -> Result<CheckTotals> {
+    let mut deleted = DeleteImpact::default();
+    let dry_run_msg_would = if args.dry_run { "Would move" } else { "Moving" };
+    let checks = check_maildirs(pool.clone(), args).await?;
+    let special = fetch_special_collections(pool.clone()).await?;
+    let mut totals = CheckTotals {
+        violating: checks.len(),
+        ..CheckTotals::default()
+    };
+
+    for check in &checks {
+        println!("{}", check.path);
+        for violation in &check.violations {
+            println!("  {}", violation.description());
+        }
+        let Some(target) = check.fix.as_ref() else {
+            continue;
+        };
+        totals.fixable += 1;
+        if !fix {
+            println!("  fixable by renaming to {}", target);
+            continue;
+        }
+        if Path::new(target).exists() {
+            println!("  Skipping: target {} already exists", target);
+            continue;
+        }
+        if args.verbose || args.dry_run {
+            println!("  {} {} to {}", dry_run_msg_would, check.path, target);
+        }
+        let name = check.path.rsplit('/').next().unwrap_or(&check.path);
+        let item_ids = fetch_items_by_remote_id(pool.clone(), check.collection_id, name).await?;
+        let items: Vec<(i64, i64)> = item_ids
+            .iter()
+            .map(|id| (*id, check.collection_id))
+            .collect();
+        if let Some(id) = find_queued_item(pool.clone(), &special, &items).await? {
+            println!("  Skipping: queued item ID {} in the outbox", id);
+            continue;
+        }
+        if args.dry_run && !args.in_place {
+            for id in &item_ids {
+                deleted.add(&report_delete_impact(pool.clone(), args, *id).await?);
+            }
+        }
+        if !args.dry_run {
+            let Some(moved) = move_mail(pool.clone(), args, &check.path, target).await? else {
+                continue;
+            };
+            for id in item_ids {
+                if args.in_place {
+                    update_item_in_place(pool.clone(), id, &moved).await?;
+                } else {
+                    deleted.add(&update_akonadi_db(pool.clone(), args, id, Some(&moved)).await?);
+                }
+            }
+        }
+        totals.fixed += 1;
+    }
+
+    if fix {
+        println!(
+            "{} maildir entries violate the rules, {} fixable, {} {}.",
+            totals.violating,
+            totals.fixable,
+            totals.fixed,
+            if args.dry_run {
+                "would be fixed"
+            } else {
+                "fixed"
+            }
+        );
+    } else {
+        println!(
+            "{} maildir entries violate the rules, {} would be fixed by --fix.",
+            totals.violating, totals.fixable
+        );
+    }
+    report_delete_totals(args, &deleted);
+    Ok(totals)
+}
@@ -0,0 +1,223 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the maildir conformance checker.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. The test data already holds three
+/// mails with an info suffix in `new`. A mail with unsorted and duplicated flags,
+/// a mail with an unknown flag letter and a directory are added to `to_be_filed/cur`.
+///
+/// # Test Cases
+///
+/// - `test_check_mail_name`: Verifies the violations found in single names.
+/// - `test_fixed_mail_name`: Verifies the conforming names.
+/// - `test_check_maildirs`: Verifies the violations found in the test data.
+/// - `test_check_maildirs_with_keywords`: Verifies that the keyword letter `a` is
+///   only accepted with `--dovecot-keywords`.
+/// - `test_check_maildirs_report`: Verifies the fixable entries counted without `--fix`.
+/// - `test_check_and_fix_maildirs`: Verifies that fixable files are renamed and
+///   their items are removed from the database.
+/// - `test_check_and_fix_maildirs_queued`: Verifies that the file of a queued
+///   Outbox item is neither renamed nor its item removed.
+///
+mod tests {
+    use crate::check::{
+        check_and_fix_maildirs, check_mail_name, check_maildirs, fixed_mail_name, CheckTotals,
+        Violation,
+    };
+    use crate::mockup::{
+        create_test_cli_args, item_present, queue_in_outbox, setup_tmp_mail_dir,
+        teardown_tmp_mail_dir,
+    };
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+
+    const FOLDER: &str = "local_mail/.inbox.directory/to_be_filed";
+    const ORIGINAL: &str = "cur/1330783242.R2038.sirius:2,S";
+    const UNSORTED: &str = "cur/1400000000.R1.sirius:2,SFS";
+    const UNKNOWN: &str = "cur/1400000001.R2.sirius:2,S~";
+    const KEYWORD: &str = "cur/1400000002.R3.sirius:2,Sa";
+    const DIRECTORY: &str = "cur/subdir";
+
+    /// Helper function adding the violating entries to the temporary mail directory.
+    fn add_violations(temp_dir: &str) -> Result<()> {
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        for file in [UNSORTED, UNKNOWN] {
+            std::fs::copy(
+                format!("{}/{}", folder, ORIGINAL),
+                format!("{}/{}", folder, file),
+            )?;
+        }
+        std::fs::create_dir_all(format!("{}/{}", folder, DIRECTORY))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_mail_name() {
+        assert!(check_mail_name("cur", "1400000000.R1.sirius:2,FS", false).is_empty());
+        assert!(check_mail_name("new", "1400000000.R1.sirius", false).is_empty());
+        assert_eq!(
+            check_mail_name("cur", "1400000000.R1.sirius:2,SFS", false),
+            vec![Violation::UnsortedFlags, Violation::DuplicateFlags]
+        );
+        assert_eq!(
+            check_mail_name("new", "1400000000.R1.sirius:2,S~", false),
+            vec![Violation::InfoInNew, Violation::UnknownFlags]
+        );
+        // Dovecot keyword letters, only accepted with `--dovecot-keywords`
+        assert!(check_mail_name("cur", "1400000000.R1.sirius:2,Sab", true).is_empty());
+        assert_eq!(
+            check_mail_name("cur", "1400000000.R1.sirius:2,Sa", false),
+            vec![Violation::UnknownFlags]
+        );
+    }
+
+    #[test]
+    fn test_fixed_mail_name() {
+        assert_eq!(
+            fixed_mail_name("cur", "1400000000.R1.sirius:2,FS", false),
+            None
+        );
+        assert_eq!(
+            fixed_mail_name("new", "1400000000.R1.sirius:2,SRS", false),
+            Some(("cur", "1400000000.R1.sirius:2,RS".to_string()))
+        );
+        assert_eq!(
+            fixed_mail_name("cur", "1400000000.R1.sirius", false),
+            Some(("new", "1400000000.R1.sirius".to_string()))
+        );
+        assert_eq!(
+            fixed_mail_name("cur", "1400000000.R1.sirius:1,S", false),
+            None
+        );
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_check_maildirs(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_violations(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let checks = check_maildirs(pool.clone(), &args).await?;
+        assert_eq!(checks.len(), 6);
+        let directory = checks.iter().find(|c| c.path.ends_with(DIRECTORY)).unwrap();
+        assert_eq!(directory.violations, vec![Violation::NotRegular]);
+        assert!(directory.fix.is_none());
+        let unknown = checks.iter().find(|c| c.path.ends_with(UNKNOWN)).unwrap();
+        assert!(unknown.fix.is_none());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_check_maildirs_with_keywords(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        std::fs::copy(
+            format!("{}/{}", folder, ORIGINAL),
+            format!("{}/{}", folder, KEYWORD),
+        )?;
+        let mut args = create_test_cli_args(&temp_dir, true);
+
+        let checks = check_maildirs(pool.clone(), &args).await?;
+        let keyword = checks.iter().find(|c| c.path.ends_with(KEYWORD)).unwrap();
+        assert_eq!(keyword.violations, vec![Violation::UnknownFlags]);
+
+        args.dovecot_keywords = true;
+        let checks = check_maildirs(pool.clone(), &args).await?;
+        assert!(!checks.iter().any(|c| c.path.ends_with(KEYWORD)));
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_check_maildirs_report(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_violations(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        let totals = check_and_fix_maildirs(pool.clone(), &args, false).await?;
+        assert_eq!(
+            totals,
+            CheckTotals {
+                violating: 6,
+                fixable: 4,
+                fixed: 0
+            }
+        );
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        assert!(Path::new(&format!("{}/{}", folder, UNSORTED)).is_file());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_check_and_fix_maildirs(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_violations(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        let totals = check_and_fix_maildirs(pool.clone(), &args, true).await?;
+        assert_eq!(totals.fixable, 4);
+        assert_eq!(totals.fixed, 4);
+
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        assert!(Path::new(&format!("{}/cur/1400000000.R1.sirius:2,FS", folder)).is_file());
+        assert!(Path::new(&format!("{}/{}", folder, UNKNOWN)).is_file());
+        assert!(Path::new(&format!("{}/cur/1491255228.R505.helios:2,PS", folder)).is_file());
+        assert!(Path::new(&format!(
+            "{}/local_mail/.inbox.directory/temporary/cur/1291727681.2020.4jNSG:2,S",
+            temp_dir
+        ))
+        .is_file());
+        // Items of the renamed files are removed for re-import
+        for id in [206, 1207, 50377] {
+            assert!(!item_present(pool.clone(), id).await?);
+        }
+        assert!(item_present(pool.clone(), 1322).await?);
+        assert!(check_maildirs(pool.clone(), &args)
+            .await?
+            .iter()
+            .all(|c| c.fix.is_none()));
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_check_and_fix_maildirs_queued(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, false);
+        queue_in_outbox(pool.clone(), 388, &[206]).await?;
+
+        check_and_fix_maildirs(pool.clone(), &args, true).await?;
+
+        let folder = format!("{}/local_mail/.inbox.directory/temporary", temp_dir);
+        assert!(Path::new(&format!("{}/new/1291727681.2020.4jNSG:2,S", folder)).is_file());
+        assert!(item_present(pool.clone(), 206).await?);
+        // Other items of the folder are still fixed
+        assert!(!item_present(pool.clone(), 50377).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -12,13 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clap::Parser;
+use crate::todoitems::selection::{parse_date, parse_regex};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug, Default)]
 #[command(name = env!("CARGO_PKG_NAME"), author = env!("CARGO_PKG_AUTHORS"), version = env!("CARGO_PKG_VERSION"), about = "fix local mail folders", long_about = None)]
 pub struct CliArgs {
     /// Perform a dry run without making actual changes
-    #[arg(short = 'D', long, default_value_t = false)]
+    #[arg(short = 'D', long, default_value_t = false, global = true)]
     pub dry_run: bool,
 
     /// Limit the number of processed messages
@@ -30,21 +31,45 @@ pub struct CliArgs {
     pub min_id: i64,
 
     /// maildir root path if not determined automatically
-    #[arg(short = 'p', long, default_value = "auto")]
+    #[arg(short = 'p', long, default_value = "auto", global = true)]
     pub maildir_path: String,
 
     /// mail cache path if not determined automatically
-    #[arg(short = 'c', long, default_value = "auto")]
+    #[arg(short = 'c', long, default_value = "auto", global = true)]
     pub mail_cache_path: String,
 
     /// Database URL if not determined automatically
-    #[arg(short = 'u', long, default_value = "auto")]
+    #[arg(short = 'u', long, default_value = "auto", global = true)]
     pub db_url: String,
 
     /// Ignore list of mails in new directories
     #[arg(short = 'i', long, default_value_t = false)]
     pub ignore_new_dirs: bool,
 
+    /// Maximum message ID to process, 0 for no maximum
+    #[arg(long, default_value_t = 0)]
+    pub max_id: i64,
+
+    /// Only process mails in this folder and its subfolders, e.g. `inbox/lists`
+    #[arg(long)]
+    pub folder: Option<String>,
+
+    /// Only process mails with an Akonadi time stamp from this date on, `YYYY-MM-DD[ HH:MM:SS]`
+    #[arg(long, value_parser = parse_date)]
+    pub since: Option<String>,
+
+    /// Only process mails with an Akonadi time stamp up to this date, `YYYY-MM-DD[ HH:MM:SS]`
+    #[arg(long, value_parser = parse_date)]
+    pub until: Option<String>,
+
+    /// Only process mails whose remote ID matches this regular expression
+    #[arg(long, value_parser = parse_regex)]
+    pub remote_id_regex: Option<String>,
+
+    /// Only process the message IDs listed in this file, `-` for stdin
+    #[arg(long)]
+    pub ids_from: Option<String>,
+
     /// Stop Kmail and Akonadi after processing
     #[arg(short = 'a', long, default_value_t = false)]
     pub stop_akonadi: bool,
@@ -54,10 +79,139 @@ pub struct CliArgs {
     pub stop_kmail: bool,
 
     /// Verbose output
-    #[arg(short = 'v', long, default_value_t = false)]
+    #[arg(short = 'v', long, default_value_t = false, global = true)]
     pub verbose: bool,
+
+    /// Set access and modification time of moved or created mails from their Date header
+    #[arg(long, default_value_t = false, global = true)]
+    pub set_mtime: bool,
+
+    /// Map an Akonadi flag to a maildir flag letter, e.g. `\DRAFT=D`, or drop it with `$REPLIED=`
+    #[arg(long, value_name = "FLAG=LETTER", global = true)]
+    pub flag_map: Vec<String>,
+
+    /// Keep flags without maildir letter as Dovecot keyword letters a-z in the file names
+    #[arg(long, default_value_t = false, global = true)]
+    pub dovecot_keywords: bool,
+
+    /// Update the Akonadi items of renamed files in place instead of deleting them
+    #[arg(long, default_value_t = false, global = true)]
+    pub in_place: bool,
+
+    /// Message-ID index cache file if not determined automatically
+    #[arg(long, default_value = "auto", global = true)]
+    pub index_cache_path: String,
+
+    /// Directory keeping the tags of deleted items if not determined automatically
+    #[arg(long, default_value = "auto", global = true)]
+    pub tag_store_path: String,
+
+    /// Rules file with per-folder selection and flag policies if not determined automatically
+    #[arg(long, default_value = "auto", global = true)]
+    pub rules_path: String,
+
+    /// Handling of mails flagged \DELETED: keep them, move them to the Trash folder or purge them
+    #[arg(long, value_enum, default_value_t = TrashPolicy::Keep, global = true)]
+    pub trash: TrashPolicy,
+
+    /// Optional maintenance command; without a command the todo items are fixed
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Index all maildir files by Message-ID and report their links to Akonadi items
+    Index,
+    /// Find duplicate mails by Message-ID and body hash and move the extra copies away
+    Dedupe {
+        /// Directory receiving the extra copies if not determined automatically
+        #[arg(short = 'q', long, default_value = "auto")]
+        quarantine_path: String,
+    },
+    /// Find duplicate pimitemtable rows, merge their flags and delete the redundant rows
+    DuplicateRows,
+    /// Report maildir files unknown to Akonadi and check whether the next sync imports them
+    Orphans,
+    /// Check all maildir folders for violations of the maildir rules
+    Check {
+        /// Rename the files violating the rules
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Deliver or remove files left behind in the tmp directories
+    Tmp {
+        /// Files modified within this number of hours are left alone
+        #[arg(long, default_value_t = 36)]
+        min_age_hours: u64,
+        /// Remove incomplete and already delivered files
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Create missing folders of collections and report folders unknown to Akonadi
+    Folders,
+    /// Reconcile the flags in the file names with the flags of the Akonadi items
+    Reconcile {
+        /// How to resolve differing flags
+        #[arg(long, value_enum, default_value_t = ConflictPolicy::Union)]
+        policy: ConflictPolicy,
+    },
+    /// Save the flags of all mails before rebuilding the Akonadi database
+    SnapshotFlags {
+        /// Snapshot file if not determined automatically
+        #[arg(short = 's', long, default_value = "auto")]
+        snapshot_path: String,
+    },
+    /// Apply the flags saved by snapshot-flags after rebuilding the Akonadi database
+    RestoreFlags {
+        /// Snapshot file if not determined automatically
+        #[arg(short = 's', long, default_value = "auto")]
+        snapshot_path: String,
+    },
+    /// Remove unreferenced payloads and stale temporary files from the Akonadi file cache
+    CacheGc {
+        /// Maildir receiving rescued mails if not determined automatically
+        #[arg(short = 'r', long, default_value = "auto")]
+        recovery_path: String,
+        /// Unreferenced files modified within this number of hours are left alone
+        #[arg(long, default_value_t = 36)]
+        min_age_hours: u64,
+    },
+    /// Re-attach the tags of deleted items to the items Akonadi re-imported
+    ReattachTags,
+}
+
+/// Resolution of differing flags in a file name and in Akonadi.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    /// Keep the flags set on either side
+    Union,
+    /// Take the flags of the file name
+    DiskWins,
+    /// Take the flags of the Akonadi item
+    DbWins,
+    /// Take the flags of the side changed last
+    Newest,
+}
+
+/// Handling of mails flagged `\DELETED`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum TrashPolicy {
+    #[default]
+    Keep,
+    Move,
+    Purge,
 }
 
 pub fn parse_args() -> CliArgs {
-    CliArgs::parse()
+    let args = CliArgs::parse();
+    if args.max_id > 0 && args.max_id < args.min_id {
+        CliArgs::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!("--max-id {} is below --min-id {}", args.max_id, args.min_id),
+            )
+            .exit();
+    }
+    args
 }
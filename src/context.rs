@@ -0,0 +1,60 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::rules::{load_folder_rules, FolderRule};
+use crate::special::{fetch_special_collections, SpecialCollections};
+use crate::todoitems::selection::load_item_ids;
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::collections::HashMap;
+
+/// State of the todo processing loaded after connecting to the database,
+/// kept apart from the parsed command line arguments.
+#[derive(Debug, Default)]
+pub struct RunContext {
+    /// Rule of each collection, resolved from the rules file
+    pub folder_rules: HashMap<i64, FolderRule>,
+    /// Special folders such as the Outbox
+    pub special_collections: SpecialCollections,
+    /// Message IDs read by `--ids-from`, `None` without it
+    pub item_ids: Option<Vec<i64>>,
+}
+
+impl RunContext {
+    /// Load the folder rules, the special folders and the message IDs of
+    /// `--ids-from` for the todo processing.
+    ///
+    /// # Arguments
+    /// - `pool`: Database connection pool
+    /// - `args`: Command line arguments
+    ///
+    /// Returns `Result<RunContext>`
+    ///
+    pub async fn load(pool: Pool<MySql>, args: &CliArgs) -> Result<Self> {
+        Ok(RunContext {
+            folder_rules: load_folder_rules(pool.clone(), args).await?,
+            special_collections: fetch_special_collections(pool).await?,
+            item_ids: load_item_ids(args)?,
+        })
+    }
+
+    /// Rule of a collection; the default rule unless the rules file says otherwise.
+    pub fn folder_rule(&self, collection_id: i64) -> FolderRule {
+        self.folder_rules
+            .get(&collection_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::header::MailHeader;
+use crate::impact::{report_delete_impact, report_delete_totals, DeleteImpact};
+use crate::mail_index::build_mail_index;
+use crate::process::execute::{move_mail, update_akonadi_db};
+use crate::process::keywords::DovecotKeywords;
+use crate::process::maildirs::{fetch_full_paths, find_collection_id, get_root_paths};
+use crate::process::target_path::{build_mail_info, split_mail_name};
+use crate::special::{fetch_special_collections, find_queued_item};
+use crate::todoitems::fetch_items_by_remote_id;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use sqlx::{MySql, Pool};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[cfg(test)]
+pub(crate) mod test_dedupe;
+
+/// Set of maildir files sharing the same Message-ID and body hash.
+#[derive(Debug)]
+pub struct DuplicateSet {
+    pub message_id: String,
+    pub files: Vec<String>,
+}
+
+/// A single copy of a duplicated mail together with the Akonadi items
+/// referring to it through their remote ID.
+#[derive(Debug)]
+struct MailCopy {
+    path: String,
+    folder: String,
+    item_ids: Vec<i64>,
+}
+
+/// Hash the body of a mail, i.e. everything after the first empty line.
+/// Headers are left out, as they differ between re-imports of the same mail.
+pub fn body_hash(mail_file: &str) -> Result<String> {
+    let data = std::fs::read(mail_file)
+        .map_err(|e| anyhow::anyhow!("Cannot read mail file: {}: {}", mail_file, e))?;
+    let body_start = data
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .map(|pos| pos + 2)
+        .or_else(|| {
+            data.windows(4)
+                .position(|w| w == b"\r\n\r\n")
+                .map(|pos| pos + 4)
+        })
+        .unwrap_or(data.len());
+    Ok(format!("{:x}", Sha256::digest(&data[body_start..])))
+}
+
+/// Directory below `$XDG_DATA_HOME/fix_local_mail/` (or `~/.local/share/...`)
+/// unless given explicitly, always with a trailing slash.
+pub fn get_data_path(path: &str, name: &str) -> Result<String> {
+    if path != "auto" {
+        return Ok(if path.ends_with('/') {
+            path.to_string()
+        } else {
+            format!("{}/", path)
+        });
+    }
+    let data_home = match std::env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => dir,
+        _ => format!("{}/.local/share", std::env::var("HOME")?),
+    };
+    Ok(format!("{}/fix_local_mail/{}/", data_home, name))
+}
+
+/// Find all sets of duplicate mails. Files are grouped by their Message-ID
+/// first; only files sharing a Message-ID are hashed and grouped by body hash.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<Vec<DuplicateSet>>`
+///
+pub async fn find_duplicate_sets(pool: Pool<MySql>, args: &CliArgs) -> Result<Vec<DuplicateSet>> {
+    let index = build_mail_index(pool.clone(), args).await?;
+    let mut message_ids: Vec<&String> = index.files_by_message_id.keys().collect();
+    message_ids.sort();
+
+    let mut sets = Vec::new();
+    for message_id in message_ids {
+        let files = &index.files_by_message_id[message_id];
+        if files.len() < 2 {
+            continue;
+        }
+        let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+        for file in files {
+            by_hash
+                .entry(body_hash(file)?)
+                .or_default()
+                .push(file.clone());
+        }
+        let mut groups: Vec<Vec<String>> = by_hash.into_values().filter(|f| f.len() > 1).collect();
+        groups.sort();
+        for mut files in groups {
+            files.sort();
+            sets.push(DuplicateSet {
+                message_id: message_id.clone(),
+                files,
+            });
+        }
+    }
+    Ok(sets)
+}
+
+/// Find duplicate mails and keep a single copy of each.
+/// - The copy known to Akonadi is kept, otherwise a copy in `cur`.
+/// - The union of the flags of all copies is merged onto the kept copy.
+///   Keyword letters of copies in other folders are translated through the
+///   `dovecot-keywords` files of both folders, unknown letters are dropped.
+/// - The extra copies are moved to the quarantine directory, keeping their
+///   path relative to the maildir root.
+/// - Akonadi items of renamed or moved files are removed from the database,
+///   so that Akonadi re-imports the kept copy.
+/// - Sets with an item queued in the Outbox are left alone.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `quarantine_path`: Directory receiving the extra copies or "auto"
+///
+/// Returns `Result<()>`
+///
+pub async fn dedupe_mails(pool: Pool<MySql>, args: &CliArgs, quarantine_path: &str) -> Result<()> {
+    let mut deleted = DeleteImpact::default();
+    let dry_run_msg_would = if args.dry_run { "Would move" } else { "Moving" };
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let roots: Vec<String> = get_root_paths(pool.clone(), args)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+    let quarantine = get_data_path(quarantine_path, "quarantine")?;
+    let sets = find_duplicate_sets(pool.clone(), args).await?;
+    let special = fetch_special_collections(pool.clone()).await?;
+    let mut extra_copies = 0;
+
+    for set in &sets {
+        let mut copies = Vec::new();
+        let mut items = Vec::new();
+        for path in &set.files {
+            let name = file_name(path);
+            let item_ids = match find_collection_id(&full_paths, path) {
+                Some(collection_id) => {
+                    let ids = fetch_items_by_remote_id(pool.clone(), collection_id, name).await?;
+                    items.extend(ids.iter().map(|id| (*id, collection_id)));
+                    ids
+                }
+                None => Vec::new(),
+            };
+            let folder = Path::new(path)
+                .parent()
+                .and_then(|p| p.parent())
+                .map(|p| format!("{}/", p.to_string_lossy()))
+                .unwrap_or_default();
+            copies.push(MailCopy {
+                path: path.clone(),
+                folder,
+                item_ids,
+            });
+        }
+        // Prefer copies known to Akonadi, then copies in cur
+        copies.sort_by_key(|c| (c.item_ids.is_empty(), !c.path.contains("/cur/")));
+
+        println!("Duplicate mail with Message-ID <{}>:", set.message_id);
+        if let Some(id) = find_queued_item(pool.clone(), &special, &items).await? {
+            println!("  Skipping: queued item ID {} in the outbox", id);
+            continue;
+        }
+        if args.verbose || args.dry_run {
+            println!("  {}", MailHeader::read(&set.files[0])?.summary());
+        }
+        for (i, copy) in copies.iter().enumerate() {
+            let (_, flags) = split_mail_name(file_name(&copy.path));
+            println!(
+                "  {} {} flags \"{}\" item ID(s) {:?}",
+                if i == 0 { "keep " } else { "extra" },
+                relative_path(&copy.path, &roots),
+                flags.iter().collect::<String>(),
+                copy.item_ids
+            );
+        }
+
+        // Move the extra copies to the quarantine directory first, as the
+        // kept copy may be renamed to the name of one of them
+        for copy in &copies[1..] {
+            let target = format!("{}{}", quarantine, relative_path(&copy.path, &roots));
+            if args.verbose || args.dry_run {
+                println!("  {} {} to {}", dry_run_msg_would, copy.path, target);
+            }
+            if !args.dry_run {
+                if let Some(moved) = move_mail(pool.clone(), args, &copy.path, &target).await? {
+                    // The tags go to the kept copy, which has the same Message-ID and body
+                    for id in &copy.item_ids {
+                        deleted
+                            .add(&update_akonadi_db(pool.clone(), args, *id, Some(&moved)).await?);
+                    }
+                }
+            } else {
+                for id in &copy.item_ids {
+                    deleted.add(&report_delete_impact(pool.clone(), args, *id).await?);
+                }
+            }
+            extra_copies += 1;
+        }
+
+        // Merge the union of all flags onto the kept copy
+        let kept = &copies[0];
+        let mut kept_keywords = DovecotKeywords::load(&kept.folder)?;
+        let mut flags = Vec::new();
+        for copy in &copies {
+            let (_, copy_flags) = split_mail_name(file_name(&copy.path));
+            if copy.folder == kept.folder {
+                flags.extend(copy_flags);
+                continue;
+            }
+            let copy_keywords = DovecotKeywords::load(&copy.folder)?;
+            for flag in copy_flags {
+                if !flag.is_ascii_lowercase() {
+                    flags.push(flag);
+                } else if let Some(letter) = copy_keywords
+                    .keyword_for(flag)
+                    .and_then(|keyword| kept_keywords.letter_for(keyword))
+                {
+                    flags.push(letter);
+                } else {
+                    println!(
+                        "  Dropping keyword letter {} of {}",
+                        flag,
+                        relative_path(&copy.path, &roots)
+                    );
+                }
+            }
+        }
+        if !args.dry_run {
+            kept_keywords.save()?;
+        }
+        let kept_name = file_name(&kept.path);
+        let (unique, _) = split_mail_name(kept_name);
+        let mail_info = build_mail_info(flags);
+        let cur_new_name = if mail_info.is_empty() { "new" } else { "cur" };
+        let target = format!("{}{}/{}{}", kept.folder, cur_new_name, unique, mail_info);
+        if target != kept.path {
+            if args.verbose || args.dry_run {
+                println!("  {} {} to {}", dry_run_msg_would, kept.path, target);
+            }
+            if !args.dry_run {
+                if let Some(moved) = move_mail(pool.clone(), args, &kept.path, &target).await? {
+                    for id in &kept.item_ids {
+                        deleted
+                            .add(&update_akonadi_db(pool.clone(), args, *id, Some(&moved)).await?);
+                    }
+                }
+            } else {
+                for id in &kept.item_ids {
+                    deleted.add(&report_delete_impact(pool.clone(), args, *id).await?);
+                }
+            }
+        }
+    }
+
+    println!(
+        "{} duplicate sets found, {} extra copies {}.",
+        sets.len(),
+        extra_copies,
+        if args.dry_run {
+            "would be moved"
+        } else {
+            "moved"
+        }
+    );
+    report_delete_totals(args, &deleted);
+    Ok(())
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Path relative to the first maildir root it is located in.
+fn relative_path(path: &str, roots: &[String]) -> String {
+    roots
+        .iter()
+        .find_map(|root| path.strip_prefix(root.as_str()))
+        .map(|p| p.trim_start_matches('/').to_string())
+        .unwrap_or_else(|| file_name(path).to_string())
+}
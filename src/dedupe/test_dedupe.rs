@@ -0,0 +1,197 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for duplicate mail detection and cleanup.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. Two extra copies of the mail
+/// `to_be_filed/cur/1330783242.R2038.sirius:2,S` (item 1322) are added under
+/// different names and with different flags.
+///
+/// # Test Cases
+///
+/// - `test_body_hash`: Verifies that the body hash ignores headers.
+/// - `test_find_duplicate_sets`: Verifies that the three copies form a single set.
+/// - `test_dedupe_mails`: Verifies that the copy known to Akonadi is kept with the
+///   union of all flags and the extra copies are moved to the quarantine directory.
+/// - `test_dedupe_mails_keywords`: Verifies that keyword letters of a copy in another
+///   folder are translated through the `dovecot-keywords` files of both folders.
+/// - `test_dedupe_mails_dry_run`: Verifies that nothing is changed in dry-run mode.
+/// - `test_dedupe_mails_queued`: Verifies that a set with a queued Outbox item is
+///   left alone.
+///
+mod tests {
+    use crate::dedupe::{body_hash, dedupe_mails, find_duplicate_sets};
+    use crate::mockup::{
+        create_test_cli_args, item_present, queue_in_outbox, setup_tmp_mail_dir,
+        teardown_tmp_mail_dir,
+    };
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+
+    const ORIGINAL: &str =
+        "local_mail/.inbox.directory/to_be_filed/cur/1330783242.R2038.sirius:2,S";
+    const COPY_FLAGGED: &str =
+        "local_mail/.inbox.directory/temporary/cur/1330783242.R77.sirius:2,F";
+    const COPY_NEW: &str = "local_mail/.inbox.directory/to_be_filed/new/1330783242.R78.sirius";
+
+    /// Helper function adding two copies of the original mail to the temporary mail directory.
+    fn add_duplicates(temp_dir: &str) -> Result<()> {
+        for copy in [COPY_FLAGGED, COPY_NEW] {
+            let target = format!("{}/{}", temp_dir, copy);
+            std::fs::create_dir_all(Path::new(&target).parent().unwrap())?;
+            std::fs::copy(format!("{}/{}", temp_dir, ORIGINAL), &target)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_body_hash() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join(format!("body_hash_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir)?;
+        let first = temp_dir.join("first").to_string_lossy().to_string();
+        let second = temp_dir.join("second").to_string_lossy().to_string();
+        let third = temp_dir.join("third").to_string_lossy().to_string();
+        std::fs::write(&first, "Message-ID: <a@b>\nX-Akonadi: 1\n\nHello\n")?;
+        std::fs::write(&second, "Message-ID: <a@b>\n\nHello\n")?;
+        std::fs::write(&third, "Message-ID: <a@b>\n\nGood bye\n")?;
+
+        assert_eq!(body_hash(&first)?, body_hash(&second)?);
+        assert_ne!(body_hash(&first)?, body_hash(&third)?);
+
+        std::fs::remove_dir_all(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_find_duplicate_sets(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_duplicates(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let sets = find_duplicate_sets(pool.clone(), &args).await?;
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].files.len(), 3);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_dedupe_mails(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_duplicates(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+        let quarantine = format!("{}/quarantine/", temp_dir);
+
+        dedupe_mails(pool.clone(), &args, &quarantine).await?;
+
+        // The copy known to Akonadi is kept with the merged flags
+        let kept = format!(
+            "{}/local_mail/.inbox.directory/to_be_filed/cur/1330783242.R2038.sirius:2,FS",
+            temp_dir
+        );
+        assert!(Path::new(&kept).is_file());
+        assert!(!Path::new(&format!("{}/{}", temp_dir, ORIGINAL)).exists());
+        // The extra copies are moved to the quarantine directory
+        for copy in [COPY_FLAGGED, COPY_NEW] {
+            assert!(!Path::new(&format!("{}/{}", temp_dir, copy)).exists());
+            let relative = copy.strip_prefix("local_mail/").unwrap();
+            assert!(Path::new(&format!("{}{}", quarantine, relative)).is_file());
+        }
+        // The item of the renamed copy is removed for re-import
+        assert!(!item_present(pool.clone(), 1322).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_dedupe_mails_keywords(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let copy = format!(
+            "{}/local_mail/.inbox.directory/temporary/cur/1330783242.R77.sirius:2,Fa",
+            temp_dir
+        );
+        std::fs::create_dir_all(Path::new(&copy).parent().unwrap())?;
+        std::fs::copy(format!("{}/{}", temp_dir, ORIGINAL), &copy)?;
+        let kept_folder = format!("{}/local_mail/.inbox.directory/to_be_filed/", temp_dir);
+        std::fs::write(format!("{}dovecot-keywords", kept_folder), "0 $Junk\n")?;
+        std::fs::write(
+            format!(
+                "{}/local_mail/.inbox.directory/temporary/dovecot-keywords",
+                temp_dir
+            ),
+            "0 $Work\n",
+        )?;
+        let args = create_test_cli_args(&temp_dir, false);
+        let quarantine = format!("{}/quarantine/", temp_dir);
+
+        dedupe_mails(pool.clone(), &args, &quarantine).await?;
+
+        // $Work is letter a in the copy's folder and letter b in the kept folder
+        let kept = format!("{}cur/1330783242.R2038.sirius:2,FSb", kept_folder);
+        assert!(Path::new(&kept).is_file());
+        assert_eq!(
+            std::fs::read_to_string(format!("{}dovecot-keywords", kept_folder))?,
+            "0 $Junk\n1 $Work\n"
+        );
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_dedupe_mails_dry_run(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_duplicates(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, true);
+        let quarantine = format!("{}/quarantine/", temp_dir);
+
+        dedupe_mails(pool.clone(), &args, &quarantine).await?;
+
+        for copy in [ORIGINAL, COPY_FLAGGED, COPY_NEW] {
+            assert!(Path::new(&format!("{}/{}", temp_dir, copy)).is_file());
+        }
+        assert!(!Path::new(&quarantine).exists());
+        assert!(item_present(pool.clone(), 1322).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_dedupe_mails_queued(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_duplicates(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+        let quarantine = format!("{}/quarantine/", temp_dir);
+        queue_in_outbox(pool.clone(), 66, &[1322]).await?;
+
+        dedupe_mails(pool.clone(), &args, &quarantine).await?;
+
+        for copy in [ORIGINAL, COPY_FLAGGED, COPY_NEW] {
+            assert!(Path::new(&format!("{}/{}", temp_dir, copy)).is_file());
+        }
+        assert!(!Path::new(&quarantine).exists());
+        assert!(item_present(pool.clone(), 1322).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
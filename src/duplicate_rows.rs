@@ -0,0 +1,294 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::impact::{report_delete_impact, report_delete_totals, DeleteImpact};
+use crate::mail_index::normalize_message_id;
+use crate::process::execute::{move_mail, update_akonadi_db};
+use crate::process::maildirs::fetch_full_paths;
+use crate::process::source_path::{get_maildir_files, get_source_file_name};
+use crate::process::target_path::{
+    assign_keyword_letters, build_mail_info, build_target_file_name, get_flag_map, get_mail_flags,
+    MailFlags,
+};
+use crate::special::{fetch_special_collections, find_queued_item};
+use crate::todoitems::TodoPimItem;
+use anyhow::Result;
+use sqlx::{FromRow, MySql, Pool};
+use std::collections::{HashMap, HashSet};
+
+#[cfg(test)]
+pub(crate) mod test_duplicate_rows;
+
+/// A `pimitemtable` row together with the number of its flags and parts.
+#[derive(Debug, Clone, FromRow)]
+pub struct PimItemRow {
+    pub id: i64,
+    pub remote_id: Option<String>,
+    pub collection_id: i64,
+    pub gid: Option<String>,
+    pub flag_count: i64,
+    pub part_count: i64,
+}
+
+impl PimItemRow {
+    fn todo_item(&self) -> TodoPimItem {
+        TodoPimItem {
+            id: self.id,
+            remote_id: self.remote_id.clone(),
+            collection_id: self.collection_id,
+        }
+    }
+}
+
+/// Set of rows describing the same mail. The first row is the survivor.
+#[derive(Debug)]
+pub struct DuplicateRows {
+    pub key: String,
+    pub rows: Vec<PimItemRow>,
+}
+
+async fn fetch_item_rows(pool: Pool<MySql>) -> Result<Vec<PimItemRow>> {
+    let rows = sqlx::query_as::<_, PimItemRow>(
+        "SELECT `id`,
+            CONVERT(`remoteId`, CHAR) AS `remote_id`,
+            `collectionId` AS `collection_id`,
+            CONVERT(`gid`, CHAR) AS `gid`,
+            (SELECT COUNT(*) FROM `pimitemflagrelation`
+             WHERE `PimItem_id` = `pimitemtable`.`id`) AS `flag_count`,
+            (SELECT COUNT(*) FROM `parttable`
+             WHERE `pimItemId` = `pimitemtable`.`id`) AS `part_count`
+        FROM `pimitemtable`
+        WHERE `mimeTypeId` = 2
+        AND (`remoteId` IS NOT NULL OR `gid` IS NOT NULL)
+        AND `collectionId` IN (
+            SELECT id FROM `collectiontable` WHERE `resourceId` = 3
+        )
+        ORDER BY `id`",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Check whether a maildir file backs the given row.
+fn is_backed(row: &PimItemRow, full_paths: &HashMap<i64, String>) -> Result<bool> {
+    let (Some(rid), Some(path)) = (row.remote_id.as_ref(), full_paths.get(&row.collection_id))
+    else {
+        return Ok(false);
+    };
+    Ok(!get_maildir_files(path, rid)?.is_empty())
+}
+
+/// Sort rows such that the row holding the most flags and parts comes first.
+fn sort_by_content(rows: &mut [PimItemRow]) {
+    rows.sort_by_key(|r| (-r.flag_count, -r.part_count, r.id));
+}
+
+/// Find sets of rows describing the same mail.
+/// - Rows with the same `(collectionId, remoteId)` all point to the same file.
+/// - Rows with the same `gid` in the same collection are duplicates if at most
+///   one of them is backed by a file; otherwise the files are duplicates as well
+///   and have to be handled by the `dedupe` command.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `full_paths`: Reference to HashMap of full paths
+///
+/// Returns `Result<Vec<DuplicateRows>>`
+///
+pub async fn find_duplicate_rows(
+    pool: Pool<MySql>,
+    full_paths: &HashMap<i64, String>,
+) -> Result<Vec<DuplicateRows>> {
+    let rows = fetch_item_rows(pool.clone()).await?;
+    let mut sets = Vec::new();
+    let mut assigned: HashSet<i64> = HashSet::new();
+
+    let mut by_remote_id: HashMap<(i64, String), Vec<PimItemRow>> = HashMap::new();
+    for row in rows.iter().filter(|r| r.remote_id.is_some()) {
+        let key = (row.collection_id, row.remote_id.clone().unwrap_or_default());
+        by_remote_id.entry(key).or_default().push(row.clone());
+    }
+    for ((collection_id, remote_id), mut group) in by_remote_id {
+        if group.len() < 2 {
+            continue;
+        }
+        sort_by_content(&mut group);
+        assigned.extend(group.iter().map(|r| r.id));
+        sets.push(DuplicateRows {
+            key: format!("remoteId {} in collection {}", remote_id, collection_id),
+            rows: group,
+        });
+    }
+
+    let mut by_gid: HashMap<(i64, String), Vec<PimItemRow>> = HashMap::new();
+    for row in rows.iter().filter(|r| !assigned.contains(&r.id)) {
+        if let Some(gid) = row.gid.as_ref().map(|g| normalize_message_id(g)) {
+            if !gid.is_empty() {
+                by_gid
+                    .entry((row.collection_id, gid))
+                    .or_default()
+                    .push(row.clone());
+            }
+        }
+    }
+    for ((collection_id, gid), group) in by_gid {
+        if group.len() < 2 {
+            continue;
+        }
+        let mut backed = Vec::new();
+        let mut ghosts = Vec::new();
+        for row in group {
+            if is_backed(&row, full_paths)? {
+                backed.push(row);
+            } else {
+                ghosts.push(row);
+            }
+        }
+        if backed.len() > 1 {
+            println!(
+                "Skipping gid {} in collection {}: {} files back these rows, see `dedupe`.",
+                gid,
+                collection_id,
+                backed.len()
+            );
+            continue;
+        }
+        sort_by_content(&mut ghosts);
+        backed.extend(ghosts);
+        sets.push(DuplicateRows {
+            key: format!("gid {} in collection {}", gid, collection_id),
+            rows: backed,
+        });
+    }
+
+    sets.sort_by_key(|s| s.rows[0].id);
+    Ok(sets)
+}
+
+/// Detect duplicate `pimitemtable` rows and repair them.
+/// - Report each set of duplicate rows and the survivor, which is the row
+///   backed by a file or holding the most flags and parts.
+/// - Merge the flags of all rows into the survivor's target file name.
+/// - Delete the redundant rows; the survivor is deleted as well if its
+///   file was renamed, such that Akonadi re-imports it.
+/// - Leave sets with an item queued in the Outbox alone.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<()>`
+///
+pub async fn repair_duplicate_rows(pool: Pool<MySql>, args: &CliArgs) -> Result<()> {
+    let mut deleted = DeleteImpact::default();
+    let dry_run_msg_would = if args.dry_run { "Would move" } else { "Moving" };
+    let dry_run_msg_delete = if args.dry_run {
+        "Would delete"
+    } else {
+        "Deleting"
+    };
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let flag_map = get_flag_map(args)?;
+    let sets = find_duplicate_rows(pool.clone(), &full_paths).await?;
+    let special = fetch_special_collections(pool.clone()).await?;
+    let mut redundant_rows = 0;
+
+    for set in &sets {
+        println!("Duplicate rows for {}:", set.key);
+        for (i, row) in set.rows.iter().enumerate() {
+            println!(
+                "  {} item ID {}: {} flags, {} parts, remote ID {:?}",
+                if i == 0 { "survivor " } else { "redundant" },
+                row.id,
+                row.flag_count,
+                row.part_count,
+                row.remote_id
+            );
+        }
+
+        let items: Vec<(i64, i64)> = set.rows.iter().map(|r| (r.id, r.collection_id)).collect();
+        if let Some(id) = find_queued_item(pool.clone(), &special, &items).await? {
+            println!("  Skipping: queued item ID {} in the outbox", id);
+            continue;
+        }
+
+        // Merge the flags of all rows into the survivor's target file name
+        let survivor = &set.rows[0];
+        let mut flags = MailFlags::default();
+        for row in &set.rows {
+            let row_flags = get_mail_flags(row.id, pool.clone(), &flag_map).await?;
+            flags.letters.extend(row_flags.letters);
+            flags.dropped.extend(row_flags.dropped);
+        }
+        if let Some(folder) = full_paths.get(&survivor.collection_id) {
+            assign_keyword_letters(&mut flags, folder, args)?;
+        }
+        let mut delete_ids: Vec<i64> = set.rows[1..].iter().map(|r| r.id).collect();
+        // File of the survivor, whose item receives the tags of all rows
+        let mut mail_file = None;
+        if is_backed(survivor, &full_paths)? {
+            let item = survivor.todo_item();
+            if let Some(source) =
+                get_source_file_name(pool.clone(), &item, &full_paths, args).await?
+            {
+                mail_file = Some(source.clone());
+                let mail_info = build_mail_info(flags.letters);
+                let target =
+                    build_target_file_name(pool.clone(), &item, &full_paths, &source, &mail_info)
+                        .await?;
+                if source != target {
+                    if args.verbose || args.dry_run {
+                        println!("  {} {} to {}", dry_run_msg_would, source, target);
+                    }
+                    if args.dry_run {
+                        delete_ids.push(survivor.id);
+                    } else if let Some(moved) =
+                        move_mail(pool.clone(), args, &source, &target).await?
+                    {
+                        mail_file = Some(moved);
+                        delete_ids.push(survivor.id);
+                    }
+                }
+            }
+        }
+
+        for id in delete_ids {
+            if args.verbose || args.dry_run {
+                println!("  {} item ID {} from database", dry_run_msg_delete, id);
+            }
+            if !args.dry_run {
+                deleted
+                    .add(&update_akonadi_db(pool.clone(), args, id, mail_file.as_deref()).await?);
+            } else {
+                deleted.add(&report_delete_impact(pool.clone(), args, id).await?);
+            }
+        }
+        redundant_rows += set.rows.len() - 1;
+    }
+
+    println!(
+        "{} sets of duplicate rows found, {} redundant rows {}.",
+        sets.len(),
+        redundant_rows,
+        if args.dry_run {
+            "would be deleted"
+        } else {
+            "deleted"
+        }
+    );
+    report_delete_totals(args, &deleted);
+    Ok(())
+}
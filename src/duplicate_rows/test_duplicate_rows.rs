@@ -0,0 +1,139 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the detection and repair of duplicate `pimitemtable` rows.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. The `tests/fixtures/duplicate_rows.sql`
+/// fixture adds row 50700 duplicating item 1322 by remote ID and row 50701 duplicating
+/// item 1207 by gid.
+///
+/// # Test Cases
+///
+/// - `test_find_duplicate_rows`: Verifies that both sets are found and the rows
+///   holding the most flags and parts or backed by a file survive.
+/// - `test_repair_duplicate_rows`: Verifies that the flags are merged into the
+///   survivor's file name and all stale rows are deleted.
+/// - `test_repair_duplicate_rows_dry_run`: Verifies that nothing is changed in
+///   dry-run mode.
+/// - `test_repair_duplicate_rows_queued`: Verifies that a set with a queued Outbox
+///   item is left alone.
+///
+mod tests {
+    use crate::duplicate_rows::{find_duplicate_rows, repair_duplicate_rows};
+    use crate::mockup::{
+        create_test_cli_args, item_present, queue_in_outbox, setup_tmp_mail_dir,
+        teardown_tmp_mail_dir,
+    };
+    use crate::process::maildirs::fetch_full_paths;
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+
+    #[sqlx::test(fixtures(
+        "../../tests/fixtures/akonadi.sql",
+        "../../tests/fixtures/duplicate_rows.sql"
+    ))]
+    async fn test_find_duplicate_rows(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, true);
+        let full_paths = fetch_full_paths(pool.clone(), &args).await?;
+
+        let sets = find_duplicate_rows(pool.clone(), &full_paths).await?;
+        assert_eq!(sets.len(), 2);
+        // Sets are ordered by the id of their survivor
+        let ids: Vec<Vec<i64>> = sets
+            .iter()
+            .map(|s| s.rows.iter().map(|r| r.id).collect())
+            .collect();
+        assert_eq!(ids, vec![vec![1207, 50701], vec![1322, 50700]]);
+        assert!(sets[0].key.starts_with("gid"));
+        assert!(sets[1].key.starts_with("remoteId"));
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures(
+        "../../tests/fixtures/akonadi.sql",
+        "../../tests/fixtures/duplicate_rows.sql"
+    ))]
+    async fn test_repair_duplicate_rows(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        repair_duplicate_rows(pool.clone(), &args).await?;
+
+        let folder = format!("{}/local_mail/.inbox.directory/to_be_filed", temp_dir);
+        // \SEEN of 1322 and \FLAGGED of 50700 are merged
+        assert!(Path::new(&format!("{}/cur/1330783242.R2038.sirius:2,FS", folder)).is_file());
+        // \SEEN and $FORWARDED of 1207 move the mail from new to cur
+        assert!(Path::new(&format!("{}/cur/1491255228.R505.helios:2,PS", folder)).is_file());
+        for id in [1207, 1322, 50700, 50701] {
+            assert!(!item_present(pool.clone(), id).await?);
+        }
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures(
+        "../../tests/fixtures/akonadi.sql",
+        "../../tests/fixtures/duplicate_rows.sql"
+    ))]
+    async fn test_repair_duplicate_rows_dry_run(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        repair_duplicate_rows(pool.clone(), &args).await?;
+
+        let folder = format!("{}/local_mail/.inbox.directory/to_be_filed", temp_dir);
+        assert!(Path::new(&format!("{}/cur/1330783242.R2038.sirius:2,S", folder)).is_file());
+        assert!(Path::new(&format!("{}/new/1491255228.R505.helios:2,PS", folder)).is_file());
+        for id in [1207, 1322, 50700, 50701] {
+            assert!(item_present(pool.clone(), id).await?);
+        }
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures(
+        "../../tests/fixtures/akonadi.sql",
+        "../../tests/fixtures/duplicate_rows.sql"
+    ))]
+    async fn test_repair_duplicate_rows_queued(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, false);
+        queue_in_outbox(pool.clone(), 66, &[1322]).await?;
+
+        repair_duplicate_rows(pool.clone(), &args).await?;
+
+        let folder = format!("{}/local_mail/.inbox.directory/to_be_filed", temp_dir);
+        assert!(Path::new(&format!("{}/cur/1330783242.R2038.sirius:2,S", folder)).is_file());
+        for id in [1322, 50700] {
+            assert!(item_present(pool.clone(), id).await?);
+        }
+        // The set without a queued item is still repaired
+        for id in [1207, 50701] {
+            assert!(!item_present(pool.clone(), id).await?);
+        }
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::dedupe::{body_hash, get_data_path};
+use crate::impact::{report_delete_impact, report_delete_totals, DeleteImpact};
+use crate::mail_index::{escape_field, normalize_message_id, read_message_id, unescape_field};
+use crate::process::execute::{move_mail, update_akonadi_db};
+use crate::process::maildirs::fetch_full_paths;
+use crate::process::source_path::get_maildir_files;
+use crate::process::target_path::{
+    assign_keyword_letters, build_mail_info, build_target_file_name, fetch_flag_names,
+    get_flag_map, map_flag_names, split_mail_name,
+};
+use crate::special::{fetch_special_collections, is_queued};
+use crate::todoitems::TodoPimItem;
+use anyhow::Result;
+use sqlx::{FromRow, MySql, Pool};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+#[cfg(test)]
+pub(crate) mod test_flag_snapshot;
+
+/// Saved flags of a mail together with the keys to find it again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlagSnapshotEntry {
+    /// Unique part of the remote ID, i.e. the file name without info suffix
+    pub remote_id: String,
+    pub message_id: String,
+    /// Hash of the mail body, see `body_hash`
+    pub content_hash: String,
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct MailItem {
+    id: i64,
+    remote_id: Option<String>,
+    collection_id: i64,
+    gid: Option<String>,
+}
+
+impl MailItem {
+    fn todo_item(&self) -> TodoPimItem {
+        TodoPimItem {
+            id: self.id,
+            remote_id: self.remote_id.clone(),
+            collection_id: self.collection_id,
+        }
+    }
+}
+
+/// Snapshot file below the data directory unless given explicitly.
+pub fn get_snapshot_file(path: &str) -> Result<String> {
+    if path != "auto" {
+        return Ok(path.to_string());
+    }
+    Ok(format!("{}flags.tsv", get_data_path(path, "snapshot")?))
+}
+
+/// Read a snapshot file written by `save_flag_snapshot`.
+pub fn load_flag_snapshot(snapshot_file: &str) -> Result<Vec<FlagSnapshotEntry>> {
+    let file = File::open(snapshot_file)
+        .map_err(|e| anyhow::anyhow!("Cannot read snapshot {}: {}", snapshot_file, e))?;
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 4 {
+            continue;
+        }
+        entries.push(FlagSnapshotEntry {
+            remote_id: unescape_field(fields[0]),
+            message_id: unescape_field(fields[1]),
+            content_hash: unescape_field(fields[2]),
+            flags: fields[3].split_whitespace().map(unescape_field).collect(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Write a snapshot file, one tab separated line per mail. Tabs and line
+/// breaks within fields are escaped as in the index cache.
+/// The file is replaced atomically, such that an interrupted run keeps
+/// the previous snapshot.
+pub fn save_flag_snapshot(snapshot_file: &str, entries: &[FlagSnapshotEntry]) -> Result<()> {
+    if let Some(parent) = std::path::Path::new(snapshot_file).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content: String = entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{}\t{}\t{}\t{}\n",
+                escape_field(&e.remote_id),
+                escape_field(&e.message_id),
+                escape_field(&e.content_hash),
+                e.flags
+                    .iter()
+                    .map(|f| escape_field(f))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        })
+        .collect();
+    let tmp_path = format!("{}.tmp", snapshot_file);
+    std::fs::write(&tmp_path, content)
+        .and_then(|_| std::fs::rename(&tmp_path, snapshot_file))
+        .map_err(|e| anyhow::anyhow!("Cannot write {}: {}", snapshot_file, e))?;
+    Ok(())
+}
+
+async fn fetch_mail_items(pool: Pool<MySql>) -> Result<Vec<MailItem>> {
+    let items = sqlx::query_as::<_, MailItem>(
+        "SELECT `id`,
+            CONVERT(`remoteId`, CHAR) AS `remote_id`,
+            `collectionId` AS `collection_id`,
+            CONVERT(`gid`, CHAR) AS `gid`
+        FROM `pimitemtable`
+        WHERE `mimeTypeId` = 2 AND `remoteId` IS NOT NULL
+        ORDER BY `id`",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(items)
+}
+
+/// Find the mail file of an item and compute its snapshot keys.
+/// Returns `None` unless exactly one file backs the item.
+fn snapshot_keys(
+    item: &MailItem,
+    full_paths: &HashMap<i64, String>,
+) -> Result<Option<(String, FlagSnapshotEntry)>> {
+    let (Some(rid), Some(folder)) = (item.remote_id.as_ref(), full_paths.get(&item.collection_id))
+    else {
+        return Ok(None);
+    };
+    let paths = get_maildir_files(folder, rid)?;
+    let [path] = paths.as_slice() else {
+        return Ok(None);
+    };
+    let path = path.clone();
+    let message_id = match item.gid.as_deref().map(normalize_message_id) {
+        Some(gid) if !gid.is_empty() => gid,
+        _ => read_message_id(&path)?.unwrap_or_default(),
+    };
+    let entry = FlagSnapshotEntry {
+        remote_id: split_mail_name(rid).0.to_string(),
+        message_id,
+        content_hash: body_hash(&path)?,
+        flags: Vec::new(),
+    };
+    Ok(Some((path, entry)))
+}
+
+/// Export the flags of all mails keyed by remote ID, Message-ID and content
+/// hash, such that they can be restored after the Akonadi database has
+/// been rebuilt. Mails without flags are left out.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `snapshot_path`: Snapshot file or "auto"
+///
+/// Returns `Result<()>`
+///
+pub async fn snapshot_flags(pool: Pool<MySql>, args: &CliArgs, snapshot_path: &str) -> Result<()> {
+    let snapshot_file = get_snapshot_file(snapshot_path)?;
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let mut entries = Vec::new();
+
+    for item in fetch_mail_items(pool.clone()).await? {
+        let flags = fetch_flag_names(item.id, pool.clone()).await?;
+        if flags.is_empty() {
+            continue;
+        }
+        let Some((_, mut entry)) = snapshot_keys(&item, &full_paths)? else {
+            if args.verbose || args.dry_run {
+                println!("Item ID {}: no mail file found, skipped.", item.id);
+            }
+            continue;
+        };
+        if args.verbose {
+            println!(
+                "Item ID {}: {} <{}> {}",
+                item.id,
+                entry.remote_id,
+                entry.message_id,
+                flags.join(" ")
+            );
+        }
+        entry.flags = flags;
+        entries.push(entry);
+    }
+
+    if !args.dry_run {
+        save_flag_snapshot(&snapshot_file, &entries)?;
+    }
+    println!(
+        "Flags of {} mails {} {}.",
+        entries.len(),
+        if args.dry_run {
+            "would be saved to"
+        } else {
+            "saved to"
+        },
+        snapshot_file
+    );
+    Ok(())
+}
+
+/// Snapshot entries by each of their keys.
+#[derive(Debug, Default)]
+struct SnapshotLookup {
+    by_remote_id: HashMap<String, Vec<usize>>,
+    by_message_id: HashMap<String, Vec<usize>>,
+    by_content_hash: HashMap<String, Vec<usize>>,
+}
+
+impl SnapshotLookup {
+    fn new(entries: &[FlagSnapshotEntry]) -> Self {
+        let mut lookup = SnapshotLookup::default();
+        for (index, entry) in entries.iter().enumerate() {
+            for (map, key) in [
+                (&mut lookup.by_remote_id, &entry.remote_id),
+                (&mut lookup.by_message_id, &entry.message_id),
+                (&mut lookup.by_content_hash, &entry.content_hash),
+            ] {
+                if !key.is_empty() {
+                    map.entry(key.clone()).or_default().push(index);
+                }
+            }
+        }
+        lookup
+    }
+
+    /// Entries matching the remote ID, otherwise the Message-ID,
+    /// otherwise the content hash.
+    fn find(&self, keys: &FlagSnapshotEntry) -> &[usize] {
+        [
+            self.by_remote_id.get(&keys.remote_id),
+            self.by_message_id.get(&keys.message_id),
+            self.by_content_hash.get(&keys.content_hash),
+        ]
+        .into_iter()
+        .flatten()
+        .next()
+        .map(|indices| indices.as_slice())
+        .unwrap_or_default()
+    }
+}
+
+/// Apply the flags of a snapshot after the Akonadi database has been rebuilt.
+/// - Find each item by remote ID, Message-ID or content hash in the snapshot.
+/// - Merge the saved flags with the current flags of the item.
+/// - Rename the file to the target name of the merged flags and delete the
+///   stale row, such that Akonadi re-imports the file with these flags.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `snapshot_path`: Snapshot file or "auto"
+///
+/// Returns `Result<()>`
+///
+pub async fn restore_flags(pool: Pool<MySql>, args: &CliArgs, snapshot_path: &str) -> Result<()> {
+    let mut deleted = DeleteImpact::default();
+    let dry_run_msg_would = if args.dry_run { "Would move" } else { "Moving" };
+    let snapshot_file = get_snapshot_file(snapshot_path)?;
+    let entries = load_flag_snapshot(&snapshot_file)?;
+    let lookup = SnapshotLookup::new(&entries);
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let flag_map = get_flag_map(args)?;
+    let special = fetch_special_collections(pool.clone()).await?;
+    let mut restored = 0;
+    let mut unmatched = 0;
+
+    for item in fetch_mail_items(pool.clone()).await? {
+        let Some((source, keys)) = snapshot_keys(&item, &full_paths)? else {
+            continue;
+        };
+        let matches = lookup.find(&keys);
+        if matches.is_empty() {
+            unmatched += 1;
+            continue;
+        }
+        let mut names = fetch_flag_names(item.id, pool.clone()).await?;
+        if is_queued(&special, item.collection_id, &names) {
+            // KMail has yet to send it
+            continue;
+        }
+        names.extend(matches.iter().flat_map(|i| entries[*i].flags.clone()));
+        names.sort();
+        names.dedup();
+        let mut flags = map_flag_names(&names, &flag_map);
+        let folder = &full_paths[&item.collection_id];
+        assign_keyword_letters(&mut flags, folder, args)?;
+        if !flags.dropped.is_empty() && (args.verbose || args.dry_run) {
+            println!(
+                "Item ID {}: flags without maildir letter cannot be restored: {}",
+                item.id,
+                flags.dropped.join(", ")
+            );
+        }
+        let todo_item = item.todo_item();
+        let mail_info = build_mail_info(flags.letters);
+        let target =
+            build_target_file_name(pool.clone(), &todo_item, &full_paths, &source, &mail_info)
+                .await?;
+        if target == source {
+            continue;
+        }
+        if args.verbose || args.dry_run {
+            println!(
+                "Item ID {}: {} {} to {}",
+                item.id, dry_run_msg_would, source, target
+            );
+        }
+        if args.dry_run {
+            deleted.add(&report_delete_impact(pool.clone(), args, item.id).await?);
+            restored += 1;
+            continue;
+        }
+        if let Some(moved) = move_mail(pool.clone(), args, &source, &target).await? {
+            deleted.add(&update_akonadi_db(pool.clone(), args, item.id, Some(&moved)).await?);
+            restored += 1;
+        }
+    }
+
+    println!(
+        "{} saved entries, flags of {} mails {}, {} mails not in the snapshot.",
+        entries.len(),
+        restored,
+        if args.dry_run {
+            "would be restored"
+        } else {
+            "restored"
+        },
+        unmatched
+    );
+    report_delete_totals(args, &deleted);
+    Ok(())
+}
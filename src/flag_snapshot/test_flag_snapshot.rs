@@ -0,0 +1,151 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for saving and restoring flags across an Akonadi rebuild.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. The snapshot file is written to
+/// this location as well. A rebuild is simulated by removing the flags of item
+/// 1322 and renaming its file `to_be_filed/cur/1330783242.R2038.sirius:2,S` to
+/// `to_be_filed/new/1330783242.R2038.sirius`.
+///
+/// # Test Cases
+///
+/// - `test_snapshot_file_round_trip`: Verifies writing and reading a snapshot file.
+/// - `test_snapshot_file_escaping`: Verifies that tabs, line breaks and backslashes in
+///   fields survive a round trip.
+/// - `test_snapshot_lookup`: Verifies the order of the keys used to find an entry.
+/// - `test_snapshot_flags`: Verifies the saved mails, keys and flags.
+/// - `test_restore_flags`: Verifies that the file of item 1322 gets its flags back
+///   and the stale row is deleted.
+///
+mod tests {
+    use crate::flag_snapshot::{
+        load_flag_snapshot, restore_flags, save_flag_snapshot, snapshot_flags, FlagSnapshotEntry,
+        SnapshotLookup,
+    };
+    use crate::mockup::{
+        create_test_cli_args, item_present, setup_tmp_mail_dir, teardown_tmp_mail_dir,
+    };
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+
+    const FOLDER: &str = "local_mail/.inbox.directory/to_be_filed/";
+    const NAME: &str = "1330783242.R2038.sirius";
+
+    fn entry(remote_id: &str, message_id: &str, content_hash: &str) -> FlagSnapshotEntry {
+        FlagSnapshotEntry {
+            remote_id: remote_id.to_string(),
+            message_id: message_id.to_string(),
+            content_hash: content_hash.to_string(),
+            flags: vec!["\\SEEN".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_snapshot_file_round_trip() -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let snapshot_file = format!("{}/snapshot/flags.tsv", temp_dir);
+        let mut entries = vec![
+            entry("1.R1.host", "a@b", "0123"),
+            entry("2.R2.host", "", ""),
+        ];
+        entries[1].flags.push("$FORWARDED".to_string());
+
+        save_flag_snapshot(&snapshot_file, &entries)?;
+        assert_eq!(load_flag_snapshot(&snapshot_file)?, entries);
+        assert!(!Path::new(&format!("{}.tmp", snapshot_file)).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_file_escaping() -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let snapshot_file = format!("{}/snapshot/flags.tsv", temp_dir);
+        let mut entries = vec![entry("1.R1\thost\\", "a\tb@c\nd", "0123")];
+        entries[0].flags.push("$Odd\tFlag".to_string());
+
+        save_flag_snapshot(&snapshot_file, &entries)?;
+        assert_eq!(std::fs::read_to_string(&snapshot_file)?.lines().count(), 1);
+        assert_eq!(load_flag_snapshot(&snapshot_file)?, entries);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_lookup() {
+        let entries = vec![
+            entry("1.R1.host", "a@b", "0123"),
+            entry("2.R2.host", "c@d", "4567"),
+            entry("3.R3.host", "c@d", "89ab"),
+        ];
+        let lookup = SnapshotLookup::new(&entries);
+        assert_eq!(lookup.find(&entry("1.R1.host", "c@d", "4567")), &[0]);
+        assert_eq!(lookup.find(&entry("9.R9.host", "c@d", "0123")), &[1, 2]);
+        assert_eq!(lookup.find(&entry("9.R9.host", "", "89ab")), &[2]);
+        assert!(lookup.find(&entry("9.R9.host", "", "")).is_empty());
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_snapshot_flags(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let snapshot_file = format!("{}/flags.tsv", temp_dir);
+        let args = create_test_cli_args(&temp_dir, false);
+
+        snapshot_flags(pool.clone(), &args, &snapshot_file).await?;
+        let entries = load_flag_snapshot(&snapshot_file)?;
+        assert_eq!(entries.len(), 5);
+        let saved = entries.iter().find(|e| e.remote_id == NAME).unwrap();
+        assert!(saved.flags.contains(&"\\SEEN".to_string()));
+        assert_eq!(saved.content_hash.len(), 64);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_restore_flags(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let snapshot_file = format!("{}/flags.tsv", temp_dir);
+        let args = create_test_cli_args(&temp_dir, false);
+        snapshot_flags(pool.clone(), &args, &snapshot_file).await?;
+
+        // Simulate the rebuild losing the flags of item 1322
+        let source = format!("{}/{}cur/{}:2,S", temp_dir, FOLDER, NAME);
+        let renamed = format!("{}/{}new/{}", temp_dir, FOLDER, NAME);
+        std::fs::rename(&source, &renamed)?;
+        sqlx::query("UPDATE pimitemtable SET remoteId = ? WHERE id = 1322")
+            .bind(NAME)
+            .execute(&pool)
+            .await?;
+        sqlx::query("DELETE FROM pimitemflagrelation WHERE PimItem_id = 1322")
+            .execute(&pool)
+            .await?;
+
+        restore_flags(pool.clone(), &args, &snapshot_file).await?;
+        assert!(Path::new(&source).exists());
+        assert!(!Path::new(&renamed).exists());
+        assert!(!item_present(pool.clone(), 1322).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::process::execute::ensure_writable_directory;
+use crate::process::maildirs::{fetch_full_paths, get_root_paths};
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::collections::HashSet;
+use std::path::Path;
+
+#[cfg(test)]
+pub(crate) mod test_folders;
+
+const MAILDIR_SUB_DIRS: [&str; 3] = ["new", "cur", "tmp"];
+
+/// Result of comparing the collection tree with the folders on disk.
+#[derive(Debug, Default)]
+pub struct FolderScan {
+    /// Collections without a directory: (collection id, path)
+    pub missing: Vec<(i64, String)>,
+    /// Existing folders lacking some of `new`, `cur` or `tmp`:
+    /// (collection id if known to Akonadi, path, missing sub-directories)
+    pub incomplete: Vec<(Option<i64>, String, Vec<&'static str>)>,
+    /// Folders on disk without a collection
+    pub unknown: Vec<String>,
+}
+
+/// Sub-directories `new`, `cur` and `tmp` missing in a maildir folder.
+pub fn missing_sub_dirs(path: &str) -> Vec<&'static str> {
+    MAILDIR_SUB_DIRS
+        .into_iter()
+        .filter(|sub_dir| !Path::new(path).join(sub_dir).is_dir())
+        .collect()
+}
+
+/// Find all maildir folders below a root directory following the KMail
+/// layout: a folder `name` lives next to the optional `.name.directory`,
+/// which holds its sub-folders. Directories named `new`, `cur` or `tmp`
+/// and hidden directories other than `.name.directory` are not folders.
+///
+/// # Arguments
+/// - `root`: maildir root path
+///
+/// Returns the folder paths with a trailing slash
+///
+pub fn find_disk_folders(root: &str) -> Vec<String> {
+    let mut folders = Vec::new();
+    let mut containers = vec![Path::new(root).to_path_buf()];
+    while let Some(container) = containers.pop() {
+        let Ok(entries) = std::fs::read_dir(&container) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                if name.ends_with(".directory") {
+                    containers.push(entry.path());
+                }
+                continue;
+            }
+            if MAILDIR_SUB_DIRS.contains(&name.as_str()) {
+                continue;
+            }
+            folders.push(format!("{}/", entry.path().to_string_lossy()));
+        }
+    }
+    folders.sort();
+    folders
+}
+
+/// Compare the collections of the local folders with the directories on disk.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<FolderScan>`
+///
+pub async fn scan_folders(pool: Pool<MySql>, args: &CliArgs) -> Result<FolderScan> {
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let roots: Vec<String> = get_root_paths(pool.clone(), args)
+        .await?
+        .into_iter()
+        .flatten()
+        .map(|root| {
+            if root.ends_with('/') {
+                root
+            } else {
+                format!("{}/", root)
+            }
+        })
+        .collect();
+    let mut collections: Vec<(i64, &String)> = full_paths
+        .iter()
+        .filter(|(_, path)| !roots.contains(path))
+        .map(|(id, path)| (*id, path))
+        .collect();
+    collections.sort_by(|a, b| a.1.cmp(b.1));
+    let known: HashSet<&String> = full_paths.values().collect();
+
+    let mut scan = FolderScan::default();
+    for (id, path) in collections {
+        if !Path::new(path).is_dir() {
+            scan.missing.push((id, path.clone()));
+            continue;
+        }
+        let missing = missing_sub_dirs(path);
+        if !missing.is_empty() {
+            scan.incomplete.push((Some(id), path.clone(), missing));
+        }
+    }
+    for root in &roots {
+        for folder in find_disk_folders(root) {
+            if known.contains(&folder) {
+                continue;
+            }
+            let missing = missing_sub_dirs(&folder);
+            if !missing.is_empty() {
+                scan.incomplete.push((None, folder.clone(), missing));
+            }
+            scan.unknown.push(folder);
+        }
+    }
+    Ok(scan)
+}
+
+/// Reconcile the collection tree with the folders on disk.
+/// - Collections without a directory get the full `new/cur/tmp` set,
+///   including the `.name.directory` parents.
+/// - Folders of collections lacking some of `new`, `cur` or `tmp` are completed.
+/// - Folders on disk without a collection and incomplete folders among them
+///   are only reported.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<()>`
+///
+pub async fn reconcile_folders(pool: Pool<MySql>, args: &CliArgs) -> Result<()> {
+    let dry_run_msg_create = if args.dry_run {
+        "Would create"
+    } else {
+        "Creating"
+    };
+    let scan = scan_folders(pool.clone(), args).await?;
+
+    if !scan.missing.is_empty() {
+        println!("Collections without a directory:");
+    }
+    for (id, path) in &scan.missing {
+        println!("  collection {}: {}", id, path);
+        for sub_dir in MAILDIR_SUB_DIRS {
+            if args.verbose || args.dry_run {
+                println!("    {} {}{}", dry_run_msg_create, path, sub_dir);
+            }
+            if !args.dry_run {
+                ensure_writable_directory(format!("{}{}", path, sub_dir))?;
+            }
+        }
+    }
+
+    if !scan.incomplete.is_empty() {
+        println!("Half-created folders:");
+    }
+    for (id, path, missing) in &scan.incomplete {
+        let Some(id) = id else {
+            println!(
+                "  unknown to Akonadi: {} lacks {}",
+                path,
+                missing.join(", ")
+            );
+            continue;
+        };
+        println!("  collection {}: {} lacks {}", id, path, missing.join(", "));
+        for sub_dir in missing {
+            if args.verbose || args.dry_run {
+                println!("    {} {}{}", dry_run_msg_create, path, sub_dir);
+            }
+            if !args.dry_run {
+                ensure_writable_directory(format!("{}{}", path, sub_dir))?;
+            }
+        }
+    }
+
+    if !scan.unknown.is_empty() {
+        println!("Folders on disk missing from Akonadi:");
+    }
+    for path in &scan.unknown {
+        println!("  {}", path);
+    }
+
+    println!(
+        "{} collections without a directory, {} half-created folders, {} folders unknown to Akonadi.",
+        scan.missing.len(),
+        scan.incomplete.len(),
+        scan.unknown.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,105 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the reconciliation of collections and folders on disk.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. Only the folders `to_be_filed`,
+/// `nirwana` and `temporary` exist there, each lacking some of `new`, `cur` and
+/// `tmp`. A folder `stray` unknown to Akonadi is added.
+///
+/// # Test Cases
+///
+/// - `test_find_disk_folders`: Verifies the folder detection in the KMail layout.
+/// - `test_scan_folders`: Verifies the missing, half-created and unknown folders.
+/// - `test_reconcile_folders`: Verifies that folders are created and completed for
+///   collections only.
+///
+mod tests {
+    use crate::folders::{find_disk_folders, missing_sub_dirs, reconcile_folders, scan_folders};
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+
+    const STRAY: &str = "local_mail/.inbox.directory/stray/";
+
+    #[test]
+    fn test_find_disk_folders() -> Result<()> {
+        let root = format!("{}/tests/data/local_mail/", env!("CARGO_MANIFEST_DIR"));
+        let folders = find_disk_folders(&root);
+        let expected: Vec<String> = ["nirwana", "temporary", "to_be_filed"]
+            .iter()
+            .map(|f| format!("{}.inbox.directory/{}/", root, f))
+            .collect();
+        assert_eq!(folders, expected);
+        assert_eq!(missing_sub_dirs(&expected[0]), vec!["cur", "tmp"]);
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_scan_folders(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        std::fs::create_dir_all(format!("{}/{}new", temp_dir, STRAY))?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let scan = scan_folders(pool.clone(), &args).await?;
+        // inbox (48) has no directory, only its sub-folder container exists
+        assert!(scan.missing.iter().any(|(id, _)| *id == 48));
+        assert!(!scan
+            .missing
+            .iter()
+            .any(|(id, _)| [66, 388, 394].contains(id)));
+        let nirwana = scan
+            .incomplete
+            .iter()
+            .find(|(id, _, _)| *id == Some(394))
+            .unwrap();
+        assert_eq!(nirwana.2, vec!["cur", "tmp"]);
+        assert!(scan
+            .incomplete
+            .iter()
+            .any(|(id, path, _)| id.is_none() && path.ends_with(STRAY)));
+        assert_eq!(scan.unknown, vec![format!("{}/{}", temp_dir, STRAY)]);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_reconcile_folders(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        std::fs::create_dir_all(format!("{}/{}new", temp_dir, STRAY))?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        reconcile_folders(pool.clone(), &args).await?;
+
+        let inbox = format!("{}/local_mail/inbox/", temp_dir);
+        assert!(missing_sub_dirs(&inbox).is_empty());
+        let nirwana = format!("{}/local_mail/.inbox.directory/nirwana/", temp_dir);
+        assert!(missing_sub_dirs(&nirwana).is_empty());
+        // Folders unknown to Akonadi are left alone
+        assert!(!Path::new(&format!("{}/{}cur", temp_dir, STRAY)).exists());
+
+        let scan = scan_folders(pool.clone(), &args).await?;
+        assert!(scan.missing.is_empty());
+        assert_eq!(scan.incomplete.len(), 1);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::mail_index::normalize_message_id;
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Datelike, NaiveDate};
+use encoding_rs::Encoding;
+use regex::Regex;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::LazyLock;
+
+#[cfg(test)]
+pub(crate) mod test_header;
+
+/// Header blocks are never read beyond this size.
+const HEADER_LIMIT: u64 = 1 << 20;
+
+/// RFC 2047 encoded word `=?charset?B|Q?text?=`.
+static ENCODED_WORD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"=\?([^?\s]+)\?([bBqQ])\?([^?\s]*)\?=").unwrap());
+
+/// `T` between the date and the time of an ISO 8601 time stamp.
+static ISO_DATE_TIME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\d)T(\d)").unwrap());
+
+/// Header block of a mail as defined by RFC 5322. The raw field values are
+/// kept as bytes with folding removed; decoding happens on access.
+#[derive(Debug, Default)]
+pub struct MailHeader {
+    /// Field names and unfolded raw values in their order
+    fields: Vec<(String, Vec<u8>)>,
+    /// A line neither a header field nor a continuation ended the block
+    pub malformed: bool,
+}
+
+impl MailHeader {
+    /// Parse the header block at the start of some mail data. An optional
+    /// mbox `From ` line is skipped. Parsing stops at the empty line ending
+    /// the header block or at the first line which is no header field.
+    pub fn parse(data: &[u8]) -> MailHeader {
+        let mut header = MailHeader::default();
+        for (i, line) in data.split(|b| *b == b'\n').enumerate() {
+            if !header.push_line(i, line) {
+                break;
+            }
+        }
+        header
+    }
+
+    /// Read the header block of a mail file binary-safely, see `parse`.
+    pub fn read(path: &str) -> Result<MailHeader> {
+        let file = File::open(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read mail file: {}: {}", path, e))?;
+        let mut reader = BufReader::new(file.take(HEADER_LIMIT));
+        let mut header = MailHeader::default();
+        let mut buffer: Vec<u8> = Vec::new();
+        for i in 0.. {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            if buffer.ends_with(b"\n") {
+                buffer.pop();
+            }
+            if !header.push_line(i, &buffer) {
+                break;
+            }
+        }
+        Ok(header)
+    }
+
+    /// Add a line of the header block, returns false at the end of the block.
+    fn push_line(&mut self, index: usize, line: &[u8]) -> bool {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            return false;
+        }
+        if index == 0 && line.starts_with(b"From ") {
+            return true;
+        }
+        if line.starts_with(b" ") || line.starts_with(b"\t") {
+            // Unfolding removes the line break only
+            match self.fields.last_mut() {
+                Some((_, value)) => value.extend_from_slice(line),
+                None => self.malformed = true,
+            }
+            return !self.malformed;
+        }
+        let name = line
+            .iter()
+            .position(|b| *b == b':')
+            .map(|pos| (&line[..pos], &line[pos + 1..]))
+            .filter(|(name, _)| !name.is_empty() && name.iter().all(|b| b.is_ascii_graphic()));
+        match name {
+            Some((name, value)) => {
+                let name = String::from_utf8_lossy(name).to_ascii_lowercase();
+                self.fields.push((name, value.to_vec()));
+                true
+            }
+            None => {
+                self.malformed = true;
+                false
+            }
+        }
+    }
+
+    /// Raw value of the first occurrence of a field, matched case-insensitively.
+    pub fn get_raw(&self, name: &str) -> Option<&[u8]> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// Decoded value of the first occurrence of a field.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.get_raw(name).map(decode_header_value)
+    }
+
+    /// Decoded values of all occurrences of a field in their order.
+    pub fn get_all(&self, name: &str) -> Vec<String> {
+        self.fields
+            .iter()
+            .filter(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| decode_header_value(value))
+            .collect()
+    }
+
+    /// Time stamp of the Date field in seconds since UNIX_EPOCH.
+    pub fn date(&self) -> Option<i64> {
+        self.get("date").and_then(|date| parse_date_value(&date))
+    }
+
+    /// Time stamp of the newest `Received:` field in seconds since
+    /// UNIX_EPOCH. The date follows the last `;` of the field.
+    pub fn received_date(&self) -> Option<i64> {
+        self.get_all("received")
+            .iter()
+            .filter_map(|received| received.rsplit_once(';'))
+            .filter_map(|(_, date)| parse_date_value(date))
+            .max()
+    }
+
+    /// Message-ID without angle brackets. A Message-ID contains no white
+    /// space, folding must not insert any.
+    pub fn message_id(&self) -> Option<String> {
+        self.get("message-id")
+            .map(|v| normalize_message_id(&v.replace(char::is_whitespace, "")))
+            .filter(|v| !v.is_empty())
+    }
+
+    pub fn from(&self) -> Option<String> {
+        self.get("from")
+    }
+
+    pub fn to(&self) -> Option<String> {
+        self.get("to")
+    }
+
+    pub fn subject(&self) -> Option<String> {
+        self.get("subject")
+    }
+
+    /// Short description of the mail for reports: `From / Subject`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} / {}",
+            self.from().unwrap_or("-".to_string()),
+            self.subject().unwrap_or("-".to_string())
+        )
+    }
+
+    /// Whether the header block is well-formed and carries at least one of
+    /// the fields `From`, `Date`, `Message-ID` or `Received`.
+    pub fn is_mail(&self) -> bool {
+        !self.malformed
+            && ["from", "date", "message-id", "received"]
+                .iter()
+                .any(|name| self.get_raw(name).is_some())
+    }
+}
+
+/// Decode raw header bytes to text. Valid UTF-8 is taken as is, anything
+/// else as Windows-1252, the usual charset of unencoded 8-bit headers.
+fn decode_raw(raw: &[u8]) -> String {
+    match std::str::from_utf8(raw) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::WINDOWS_1252
+            .decode_without_bom_handling(raw)
+            .0
+            .into_owned(),
+    }
+}
+
+/// Decode a raw header value: RFC 2047 encoded words `=?charset?B|Q?...?=`
+/// in any charset known to the WHATWG encoding standard are decoded, white
+/// space between adjacent encoded words is dropped, and the result is trimmed.
+/// Encoded words of the same charset are joined before decoding, such that
+/// characters split across words survive. Unknown charsets are kept encoded.
+pub fn decode_header_value(raw: &[u8]) -> String {
+    let text = decode_raw(raw);
+    let mut result = String::new();
+    // Bytes of adjacent encoded words not yet decoded
+    let mut pending: Option<(&'static Encoding, Vec<u8>)> = None;
+    let mut last = 0;
+    for caps in ENCODED_WORD.captures_iter(&text) {
+        let word = caps.get(0).unwrap();
+        let gap = &text[last..word.start()];
+        last = word.end();
+        let charset = caps[1].split('*').next().unwrap_or_default();
+        let decoded =
+            Encoding::for_label(charset.as_bytes()).zip(decode_encoded_text(&caps[2], &caps[3]));
+        let Some((encoding, bytes)) = decoded else {
+            flush_encoded_words(&mut result, &mut pending);
+            result.push_str(gap);
+            result.push_str(word.as_str());
+            continue;
+        };
+        match pending.as_mut() {
+            Some((pending_encoding, pending_bytes))
+                if gap.trim().is_empty() && *pending_encoding == encoding =>
+            {
+                pending_bytes.extend(bytes);
+            }
+            _ => {
+                let adjacent = pending.is_some() && gap.trim().is_empty();
+                flush_encoded_words(&mut result, &mut pending);
+                if !adjacent {
+                    result.push_str(gap);
+                }
+                pending = Some((encoding, bytes));
+            }
+        }
+    }
+    flush_encoded_words(&mut result, &mut pending);
+    result.push_str(&text[last..]);
+    result.trim().to_string()
+}
+
+/// Append the decoded pending encoded words.
+fn flush_encoded_words(result: &mut String, pending: &mut Option<(&'static Encoding, Vec<u8>)>) {
+    if let Some((encoding, bytes)) = pending.take() {
+        result.push_str(&encoding.decode_without_bom_handling(&bytes).0);
+    }
+}
+
+/// Decode the text of an encoded word in `B` (base64) or `Q` encoding.
+fn decode_encoded_text(encoding: &str, text: &str) -> Option<Vec<u8>> {
+    if encoding.eq_ignore_ascii_case("b") {
+        let engine = base64::engine::GeneralPurpose::new(
+            &base64::alphabet::STANDARD,
+            base64::engine::GeneralPurposeConfig::new()
+                .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent),
+        );
+        return engine.decode(text).ok();
+    }
+    let mut bytes = Vec::new();
+    let mut chars = text.bytes();
+    while let Some(b) = chars.next() {
+        match b {
+            b'_' => bytes.push(b' '),
+            b'=' => {
+                let hex = [chars.next()?, chars.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            _ => bytes.push(b),
+        }
+    }
+    Some(bytes)
+}
+
+/// Month names and abbreviations in English, German, French, Spanish,
+/// Italian and Dutch, matched by prefix.
+const MONTH_PREFIXES: [(&str, u32); 36] = [
+    ("jan", 1),
+    ("ene", 1),
+    ("gen", 1),
+    ("feb", 2),
+    ("fév", 2),
+    ("fev", 2),
+    ("mar", 3),
+    ("mär", 3),
+    ("mrz", 3),
+    ("mrt", 3),
+    ("apr", 4),
+    ("avr", 4),
+    ("abr", 4),
+    ("may", 5),
+    ("mai", 5),
+    ("mei", 5),
+    ("mag", 5),
+    ("jun", 6),
+    ("juin", 6),
+    ("giu", 6),
+    ("jul", 7),
+    ("juil", 7),
+    ("lug", 7),
+    ("aug", 8),
+    ("aoû", 8),
+    ("aou", 8),
+    ("ago", 8),
+    ("sep", 9),
+    ("set", 9),
+    ("oct", 10),
+    ("okt", 10),
+    ("ott", 10),
+    ("nov", 11),
+    ("dec", 12),
+    ("dez", 12),
+    ("dic", 12),
+];
+
+/// Named time zones found in mail dates with their offset in minutes.
+const ZONE_OFFSETS: [(&str, i32); 22] = [
+    ("ut", 0),
+    ("utc", 0),
+    ("gmt", 0),
+    ("z", 0),
+    ("wet", 0),
+    ("bst", 60),
+    ("cet", 60),
+    ("met", 60),
+    ("mez", 60),
+    ("cest", 120),
+    ("mest", 120),
+    ("mesz", 120),
+    ("eet", 120),
+    ("eest", 180),
+    ("est", -300),
+    ("edt", -240),
+    ("cst", -360),
+    ("cdt", -300),
+    ("mst", -420),
+    ("mdt", -360),
+    ("pst", -480),
+    ("pdt", -420),
+];
+
+/// Parse the value of a Date header in seconds since UNIX_EPOCH.
+/// Besides RFC 5322 dates, obsolete forms are accepted: missing weekday or
+/// seconds, two-digit years, named or missing time zones, comments,
+/// `asctime` and ISO 8601 layouts, and localised month names.
+/// Dates without a time zone are taken as UTC.
+pub fn parse_date_value(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc2822(value) {
+        return Some(date_time.timestamp());
+    }
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.timestamp());
+    }
+
+    // Drop comments like "(CEST)" and split into tokens
+    let mut text = String::new();
+    let mut depth = 0;
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = (depth - 1).max(0),
+            _ if depth == 0 => text.push(c),
+            _ => {}
+        }
+    }
+    // Separate ISO 8601 date and time and keep weekdays apart from the day
+    let text = ISO_DATE_TIME.replace_all(&text, "$1 $2").replace(',', ", ");
+    let (mut day, mut month, mut year) = (None, None, None);
+    let (mut hms, mut offset) = (None, None);
+    for token in text.split_whitespace() {
+        if token.ends_with(',') && token.chars().any(|c| c.is_alphabetic()) {
+            // Weekday
+            continue;
+        }
+        let token = token.trim_matches([',', '.']);
+        let lower = token.to_lowercase();
+        if let Some((y, m, d)) = parse_iso_date(token) {
+            (year, month, day) = (Some(y), Some(m), Some(d));
+        } else if token.contains(':') && token.starts_with(|c: char| c.is_ascii_digit()) {
+            let (time, zone) = match token.find(['+', '-']) {
+                Some(pos) => (&token[..pos], Some(&token[pos..])),
+                None => (token, None),
+            };
+            let parts: Vec<u32> = time.split(':').filter_map(|p| p.parse().ok()).collect();
+            if parts.len() >= 2 {
+                hms = Some((parts[0], parts[1], parts.get(2).copied().unwrap_or(0)));
+            }
+            if let Some(zone) = zone {
+                offset = parse_zone_offset(zone).or(offset);
+            }
+        } else if token.starts_with(['+', '-']) {
+            offset = parse_zone_offset(token).or(offset);
+        } else if let Some((_, minutes)) = ZONE_OFFSETS.iter().find(|(name, _)| *name == lower) {
+            offset = offset.or(Some(*minutes));
+        } else if let Ok(number) = token.parse::<i32>() {
+            if day.is_none() && token.len() <= 2 && (1..=31).contains(&number) {
+                day = Some(number as u32);
+            } else if year.is_none() {
+                year = Some(match (token.len(), number) {
+                    (1 | 2, 0..=49) => number + 2000,
+                    (1..=3, _) => number + 1900,
+                    _ => number,
+                });
+            }
+        } else if month.is_none() && lower.chars().count() >= 3 {
+            month = MONTH_PREFIXES
+                .iter()
+                .find(|(prefix, _)| lower.starts_with(prefix))
+                .map(|(_, month)| *month);
+        }
+    }
+
+    let (hour, minute, second) = hms.unwrap_or((0, 0, 0));
+    let date_time =
+        NaiveDate::from_ymd_opt(year?, month?, day?)?.and_hms_opt(hour, minute, second.min(59))?;
+    Some(date_time.and_utc().timestamp() - i64::from(offset.unwrap_or(0)) * 60)
+}
+
+/// Parse an ISO 8601 date `YYYY-MM-DD`.
+fn parse_iso_date(token: &str) -> Option<(i32, u32, u32)> {
+    let date = NaiveDate::parse_from_str(token, "%Y-%m-%d").ok()?;
+    Some((date.year(), date.month(), date.day()))
+}
+
+/// Parse a numeric time zone `+hhmm`, `+hh:mm` or `+hh` in minutes.
+fn parse_zone_offset(zone: &str) -> Option<i32> {
+    let sign = if zone.starts_with('-') { -1 } else { 1 };
+    let digits: String = zone[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    Some(sign * (hours * 60 + minutes))
+}
@@ -0,0 +1,138 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the mail header parser.
+///
+/// # Test Setup
+///
+/// The tests parse header blocks given as byte strings and the mails in `tests/data`.
+///
+/// # Test Cases
+///
+/// - `test_parse_header`: Verifies case-insensitive field lookup, unfolding, multiple
+///   occurrences, and the end of the header block.
+/// - `test_parse_binary_header`: Verifies that 8-bit headers and an mbox `From ` line
+///   are accepted and malformed blocks are detected.
+/// - `test_decode_header_value`: Verifies RFC 2047 decoding in several charsets,
+///   including characters split across encoded words.
+/// - `test_read_header`: Verifies reading the header block of a mail file.
+/// - `test_parse_date_value`: Verifies parsing of RFC 5322, obsolete and localised dates.
+///
+mod tests {
+    use crate::header::{decode_header_value, parse_date_value, MailHeader};
+    use anyhow::Result;
+
+    const HEADER: &[u8] = b"Received: from a by b; Mon, 3 Apr 2017 21:33:50 +0000\r
+Received: from c\r
+ by a; Mon, 3 Apr 2017 21:33:49 +0000\r
+SUBJECT: Hello\r
+\tWorld\r
+message-id: <abc@\r
+ example.org>\r
+\r
+To: not a header\r
+";
+
+    #[test]
+    fn test_parse_header() {
+        let header = MailHeader::parse(HEADER);
+        assert!(!header.malformed);
+        assert_eq!(header.subject(), Some("Hello\tWorld".to_string()));
+        assert_eq!(header.message_id(), Some("abc@example.org".to_string()));
+        assert_eq!(header.get_all("received").len(), 2);
+        assert_eq!(header.received_date(), Some(1491255230));
+        assert_eq!(header.date(), None);
+        assert_eq!(header.to(), None);
+        assert_eq!(header.summary(), "- / Hello\tWorld");
+        assert!(header.is_mail());
+    }
+
+    #[test]
+    fn test_parse_binary_header() {
+        let header = MailHeader::parse(b"From someone Mon Apr  3 21:33:48 2017\nFrom: J\xfcrgen <j@example.org>\nSubject: Gr\xfc\xdfe\n\nbody");
+        assert!(header.is_mail());
+        assert_eq!(header.from(), Some("Jürgen <j@example.org>".to_string()));
+        assert_eq!(header.subject(), Some("Grüße".to_string()));
+
+        let header = MailHeader::parse(b"\x89PNG\r\n\x1a\n");
+        assert!(header.malformed);
+        assert!(!header.is_mail());
+        let header = MailHeader::parse(b"Subject: only\n\n");
+        assert!(!header.is_mail());
+    }
+
+    #[test]
+    fn test_decode_header_value() {
+        assert_eq!(
+            decode_header_value(b"=?UTF-8?B?R3LDvMOfZQ==?= aus =?iso-8859-1?q?M=FCnchen?="),
+            "Grüße aus München"
+        );
+        // Adjacent encoded words are joined, the ü is split across them
+        assert_eq!(
+            decode_header_value(b"=?utf-8?q?Gr=C3?=  =?utf-8?q?=BC=C3=9Fe_zur=C3=BCck?="),
+            "Grüße zurück"
+        );
+        assert_eq!(decode_header_value(b"=?koi8-r?B?8NLJ18XU?="), "Привет");
+        assert_eq!(decode_header_value(b"=?Shift_JIS?B?k/qWe4zq?="), "日本語");
+        assert_eq!(decode_header_value(b"=?windows-1252?Q?=80_5?="), "€ 5");
+        assert_eq!(
+            decode_header_value(b"=?x-unknown?Q?abc?= x"),
+            "=?x-unknown?Q?abc?= x"
+        );
+        assert_eq!(decode_header_value(b"  plain text "), "plain text");
+    }
+
+    #[test]
+    fn test_read_header() -> Result<()> {
+        let path = format!(
+            "{}/tests/data/local_mail/.inbox.directory/to_be_filed/new/1491255228.R505.helios:2,PS",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let header = MailHeader::read(&path)?;
+        assert!(header.is_mail());
+        assert_eq!(header.date(), Some(1491255228));
+        assert!(header.from().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_date_value() {
+        let expected = Some(1491255228);
+        for date in [
+            "Mon, 03 Apr 2017 23:33:48 +0200",
+            "Mon, 3 Apr 2017 23:33:48 +0200 (CEST)",
+            "3 Apr 17 23:33:48 +0200",
+            "Mon,  3 Apr 2017 21:33:48 GMT",
+            "Mon, 3 Apr 2017 23:33:48 CEST",
+            "Mon Apr  3 21:33:48 2017",
+            "2017-04-03T23:33:48+02:00",
+            "2017-04-03 23:33:48 +0200",
+            "Mo, 03 Apr. 2017 23:33:48 +0200",
+            "lun., 3 avr. 2017 23:33:48 +0200",
+        ] {
+            assert_eq!(parse_date_value(date), expected, "{}", date);
+        }
+        assert_eq!(
+            parse_date_value("Di, 3 Dez 2019 10:00 +0100"),
+            Some(1575363600)
+        );
+        assert_eq!(
+            parse_date_value("Thu, 7 Mär 2024 08:00:00 +0100"),
+            Some(1709794800)
+        );
+        assert_eq!(parse_date_value("no date"), None);
+        assert_eq!(parse_date_value("Mon, 31 Feb 2017 23:33:48 +0200"), None);
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::process::target_path::fetch_flag_names;
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+pub(crate) mod test_impact;
+
+/// Akonadi-only data removed together with items by the delete cascade.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DeleteImpact {
+    pub items: usize,
+    /// Parts by `ns:name` of their `parttypetable` entry
+    pub parts: BTreeMap<String, usize>,
+    pub flags: BTreeMap<String, usize>,
+    /// Tags by their gid
+    pub tags: BTreeMap<String, usize>,
+    /// Rows of `relationtable` on either side
+    pub relations: usize,
+    /// `entityannotations` attribute parts
+    pub annotations: usize,
+    /// Memberships in virtual collections, `collectionpimitemrelation`
+    pub virtual_collections: usize,
+}
+
+impl DeleteImpact {
+    pub fn add(&mut self, other: &DeleteImpact) {
+        self.items += other.items;
+        for (map, other_map) in [
+            (&mut self.parts, &other.parts),
+            (&mut self.flags, &other.flags),
+            (&mut self.tags, &other.tags),
+        ] {
+            for (name, count) in other_map {
+                *map.entry(name.clone()).or_default() += count;
+            }
+        }
+        self.relations += other.relations;
+        self.annotations += other.annotations;
+        self.virtual_collections += other.virtual_collections;
+    }
+
+    /// One line listing the non-empty categories, e.g.
+    /// `parts: ATR:HIDDEN, PLD:RFC822 (2); flags: \SEEN; relations: 1`.
+    pub fn describe(&self) -> String {
+        let mut segments = Vec::new();
+        for (label, map) in [
+            ("parts", &self.parts),
+            ("flags", &self.flags),
+            ("tags", &self.tags),
+        ] {
+            if !map.is_empty() {
+                let names: Vec<String> = map
+                    .iter()
+                    .map(|(name, count)| match count {
+                        1 => name.clone(),
+                        _ => format!("{} ({})", name, count),
+                    })
+                    .collect();
+                segments.push(format!("{}: {}", label, names.join(", ")));
+            }
+        }
+        for (label, count) in [
+            ("relations", self.relations),
+            ("annotations", self.annotations),
+            ("virtual collections", self.virtual_collections),
+        ] {
+            if count > 0 {
+                segments.push(format!("{}: {}", label, count));
+            }
+        }
+        if segments.is_empty() {
+            "nothing else".to_string()
+        } else {
+            segments.join("; ")
+        }
+    }
+}
+
+/// Collect what deleting an item cascades away.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `id`: Pim item id
+///
+/// Returns `Result<DeleteImpact>`
+///
+pub async fn fetch_delete_impact(pool: Pool<MySql>, id: i64) -> Result<DeleteImpact> {
+    let mut impact = DeleteImpact {
+        items: 1,
+        ..DeleteImpact::default()
+    };
+    let parts: Vec<(String,)> = sqlx::query_as(
+        "SELECT CONCAT(CONVERT(`parttypetable`.`ns`, CHAR), ':',
+                       CONVERT(`parttypetable`.`name`, CHAR))
+        FROM `parttable` JOIN `parttypetable`
+          ON `parttable`.`partTypeId` = `parttypetable`.`id`
+        WHERE `parttable`.`pimItemId` = ?",
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await?;
+    for (name,) in parts {
+        if name.eq_ignore_ascii_case("ATR:entityannotations") {
+            impact.annotations += 1;
+        }
+        *impact.parts.entry(name).or_default() += 1;
+    }
+    for name in fetch_flag_names(id, pool.clone()).await? {
+        *impact.flags.entry(name).or_default() += 1;
+    }
+    let tags: Vec<(String,)> = sqlx::query_as(
+        "SELECT CONVERT(`tagtable`.`gid`, CHAR)
+        FROM `pimitemtagrelation` JOIN `tagtable`
+          ON `pimitemtagrelation`.`Tag_id` = `tagtable`.`id`
+        WHERE `pimitemtagrelation`.`PimItem_id` = ?",
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await?;
+    for (gid,) in tags {
+        *impact.tags.entry(gid).or_default() += 1;
+    }
+    let (relations,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM `relationtable` WHERE `leftId` = ? OR `rightId` = ?")
+            .bind(id)
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+    impact.relations = relations as usize;
+    let (memberships,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM `collectionpimitemrelation` WHERE `PimItem_id` = ?")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+    impact.virtual_collections = memberships as usize;
+    Ok(impact)
+}
+
+/// Report what deleting an item cascades away. Called for each delete, and
+/// in dry-run mode for each delete a real run would do; the caller sums the
+/// impacts up for `report_delete_totals`.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `id`: Pim item id
+///
+/// Returns `Result<DeleteImpact>`
+///
+pub async fn report_delete_impact(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    id: i64,
+) -> Result<DeleteImpact> {
+    let impact = fetch_delete_impact(pool, id).await?;
+    if args.verbose || args.dry_run {
+        println!(
+            "Item ID {}: deleting {} {}",
+            id,
+            if args.dry_run { "would drop" } else { "drops" },
+            impact.describe()
+        );
+    }
+    Ok(impact)
+}
+
+/// Print the totals of all deletes of a command, if any.
+pub fn report_delete_totals(args: &CliArgs, totals: &DeleteImpact) {
+    if totals.items == 0 {
+        return;
+    }
+    println!(
+        "Deleting {} items {} {}.",
+        totals.items,
+        if args.dry_run {
+            "would drop"
+        } else {
+            "dropped"
+        },
+        totals.describe()
+    );
+}
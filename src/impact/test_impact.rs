@@ -0,0 +1,98 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the report of what deleting an item cascades away.
+///
+/// # Test Setup
+///
+/// Item 1322 of the fixture has the payload parts `PLD:ENVELOPE`, `PLD:HEAD`
+/// and `PLD:RFC822` and the flags `\SEEN` and `$ATTACHMENT`. The database test
+/// adds an `entityannotations` part, a relation to item 1207 and a membership
+/// in collection 47.
+///
+/// # Test Cases
+///
+/// - `test_describe_impact`: Verifies adding up and describing impacts.
+/// - `test_fetch_delete_impact`: Verifies the impact collected for item 1322.
+///
+mod tests {
+    use crate::impact::{fetch_delete_impact, DeleteImpact};
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+
+    #[test]
+    fn test_describe_impact() {
+        let mut totals = DeleteImpact::default();
+        assert_eq!(totals.describe(), "nothing else");
+
+        let mut impact = DeleteImpact {
+            items: 1,
+            relations: 1,
+            ..DeleteImpact::default()
+        };
+        impact.parts.insert("PLD:RFC822".to_string(), 1);
+        impact.flags.insert("\\SEEN".to_string(), 1);
+        assert_eq!(
+            impact.describe(),
+            "parts: PLD:RFC822; flags: \\SEEN; relations: 1"
+        );
+
+        totals.add(&impact);
+        totals.add(&impact);
+        assert_eq!(totals.items, 2);
+        assert_eq!(
+            totals.describe(),
+            "parts: PLD:RFC822 (2); flags: \\SEEN (2); relations: 2"
+        );
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_fetch_delete_impact(pool: Pool<MySql>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO parttable (pimItemId, partTypeId, data, datasize) VALUES (1322, 10, 'x', 1)",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query("INSERT INTO relationtable (leftId, rightId) VALUES (1322, 1207)")
+            .execute(&pool)
+            .await?;
+        sqlx::query(
+            "INSERT INTO collectionpimitemrelation (Collection_id, PimItem_id) VALUES (47, 1322)",
+        )
+        .execute(&pool)
+        .await?;
+
+        let impact = fetch_delete_impact(pool.clone(), 1322).await?;
+        assert_eq!(impact.items, 1);
+        assert_eq!(
+            impact.parts.keys().collect::<Vec<_>>(),
+            vec![
+                "ATR:entityannotations",
+                "PLD:ENVELOPE",
+                "PLD:HEAD",
+                "PLD:RFC822"
+            ]
+        );
+        assert_eq!(impact.flags.len(), 2);
+        assert!(impact.tags.is_empty());
+        assert_eq!(impact.annotations, 1);
+        assert_eq!(impact.relations, 1);
+        assert_eq!(impact.virtual_collections, 1);
+
+        // Item 1207 is on the other side of the relation
+        assert_eq!(fetch_delete_impact(pool.clone(), 1207).await?.relations, 1);
+        Ok(())
+    }
+}
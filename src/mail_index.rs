@@ -0,0 +1,349 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::header::MailHeader;
+use crate::process::maildirs::get_root_paths;
+use anyhow::Result;
+use sqlx::{FromRow, MySql, Pool};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::time::UNIX_EPOCH;
+
+#[cfg(test)]
+pub(crate) mod test_mail_index;
+
+/// Index of all maildir files keyed by their Message-ID, joined against
+/// `pimitemtable`.`gid`, which carries the Message-ID for mails.
+#[derive(Debug, Default)]
+pub struct MailIndex {
+    /// Message-ID -> maildir files carrying this Message-ID
+    pub files_by_message_id: HashMap<String, Vec<String>>,
+    /// maildir file -> Message-ID
+    pub message_id_by_file: HashMap<String, String>,
+    /// gid (Message-ID) -> pim item ids
+    pub items_by_gid: HashMap<String, Vec<i64>>,
+    /// pim item id -> gid (Message-ID)
+    pub gid_by_item: HashMap<i64, String>,
+}
+
+impl MailIndex {
+    /// All maildir files carrying the Message-ID of the given pim item.
+    pub fn files_for_item(&self, id: i64) -> Vec<String> {
+        self.gid_by_item
+            .get(&id)
+            .and_then(|gid| self.files_by_message_id.get(gid))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// All pim items whose gid matches the Message-ID of the given maildir file.
+    pub fn items_for_file(&self, path: &str) -> Vec<i64> {
+        self.message_id_by_file
+            .get(path)
+            .and_then(|message_id| self.items_by_gid.get(message_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Entry of the on-disk index cache. A file is only scanned again
+/// if its size or modification time differs from the cached values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexCacheEntry {
+    pub size: u64,
+    pub mtime: u64,
+    pub message_id: String,
+}
+
+#[derive(Debug, FromRow)]
+struct PimItemGid {
+    id: i64,
+    gid: String,
+}
+
+pub fn get_index_cache_path(args: &CliArgs) -> Result<String> {
+    if args.index_cache_path != "auto" {
+        return Ok(args.index_cache_path.clone());
+    }
+    let cache_home = match std::env::var("XDG_CACHE_HOME") {
+        Ok(dir) if !dir.is_empty() => dir,
+        _ => format!("{}/.cache", std::env::var("HOME")?),
+    };
+    Ok(format!("{}/fix_local_mail/message_ids.tsv", cache_home))
+}
+
+/// Strip angle brackets and white space from a Message-ID or gid.
+pub fn normalize_message_id(message_id: &str) -> String {
+    message_id
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim()
+        .to_string()
+}
+
+/// Read the Message-ID header of a mail file.
+pub fn read_message_id(mail_file: &str) -> Result<Option<String>> {
+    Ok(MailHeader::read(mail_file)?.message_id())
+}
+
+/// Escape the characters separating the fields and lines of the index cache.
+pub(crate) fn escape_field(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Undo `escape_field`.
+pub(crate) fn unescape_field(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+pub fn load_index_cache(cache_file: &str) -> HashMap<String, IndexCacheEntry> {
+    let mut entries = HashMap::new();
+    let Ok(file) = File::open(cache_file) else {
+        return entries;
+    };
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 4 {
+            continue;
+        }
+        if let (Ok(size), Ok(mtime)) = (fields[1].parse(), fields[2].parse()) {
+            entries.insert(
+                unescape_field(fields[0]),
+                IndexCacheEntry {
+                    size,
+                    mtime,
+                    message_id: unescape_field(fields[3]),
+                },
+            );
+        }
+    }
+    entries
+}
+
+pub fn save_index_cache(
+    cache_file: &str,
+    entries: &HashMap<String, IndexCacheEntry>,
+) -> Result<()> {
+    if let Some(parent) = std::path::Path::new(cache_file).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(cache_file)?;
+    for (path, entry) in entries {
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}",
+            escape_field(path),
+            entry.size,
+            entry.mtime,
+            escape_field(&entry.message_id)
+        )?;
+    }
+    Ok(())
+}
+
+/// Collect all mail files in `new` and `cur` directories below the maildir roots
+pub async fn list_maildir_files(pool: Pool<MySql>, args: &CliArgs) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let directories = get_root_paths(pool.clone(), args).await?;
+    for dir in directories.into_iter().flatten() {
+        for entry in walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let in_mail_dir = entry
+                .path()
+                .parent()
+                .and_then(|p| p.file_name())
+                .is_some_and(|name| name == "new" || name == "cur");
+            if in_mail_dir {
+                files.push(entry.path().to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Build the Message-ID index of all maildir files and join it against
+/// `pimitemtable`.`gid`. Headers are only scanned for files which are new
+/// or changed since the last run; the results are cached on disk.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<MailIndex>`
+///
+pub async fn build_mail_index(pool: Pool<MySql>, args: &CliArgs) -> Result<MailIndex> {
+    let cache_file = get_index_cache_path(args)?;
+    let cached = load_index_cache(&cache_file);
+    let mut entries: HashMap<String, IndexCacheEntry> = HashMap::new();
+    let mut index = MailIndex::default();
+    let mut scanned = 0;
+
+    for path in list_maildir_files(pool.clone(), args).await? {
+        // Files may be moved or deleted by Akonadi or another client meanwhile
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", path, e);
+                continue;
+            }
+        };
+        let size = metadata.len();
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let entry = match cached.get(&path) {
+            Some(entry) if entry.size == size && entry.mtime == mtime => entry.clone(),
+            _ => {
+                let message_id = match read_message_id(&path) {
+                    Ok(message_id) => message_id.unwrap_or_default(),
+                    Err(e) => {
+                        eprintln!("Skipping {}: {}", path, e);
+                        continue;
+                    }
+                };
+                scanned += 1;
+                IndexCacheEntry {
+                    size,
+                    mtime,
+                    message_id,
+                }
+            }
+        };
+        if !entry.message_id.is_empty() {
+            index
+                .files_by_message_id
+                .entry(entry.message_id.clone())
+                .or_default()
+                .push(path.clone());
+            index
+                .message_id_by_file
+                .insert(path.clone(), entry.message_id.clone());
+        }
+        entries.insert(path, entry);
+    }
+    if args.verbose {
+        println!(
+            "Indexed {} mail files, {} headers scanned, {} taken from cache {}.",
+            entries.len(),
+            scanned,
+            entries.len() - scanned,
+            cache_file
+        );
+    }
+    save_index_cache(&cache_file, &entries)?;
+
+    // Join against the gid column of all mails in local folders
+    let rows: Vec<PimItemGid> = sqlx::query_as(
+        "SELECT `id`, CONVERT(`gid`, CHAR) AS `gid`
+         FROM `pimitemtable`
+         WHERE `mimeTypeId` = 2
+         AND `gid` IS NOT NULL
+         AND `collectionId` IN (SELECT id FROM `collectiontable` WHERE `resourceId` = 3)
+         ORDER BY `id`",
+    )
+    .fetch_all(&pool)
+    .await?;
+    for row in rows {
+        let gid = normalize_message_id(&row.gid);
+        if gid.is_empty() {
+            continue;
+        }
+        index
+            .items_by_gid
+            .entry(gid.clone())
+            .or_default()
+            .push(row.id);
+        index.gid_by_item.insert(row.id, gid);
+    }
+
+    Ok(index)
+}
+
+/// Report the links between pim items and maildir files found by the
+/// Message-ID index: items without files and files without items.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<()>`
+///
+pub async fn report_mail_index(pool: Pool<MySql>, args: &CliArgs) -> Result<()> {
+    let index = build_mail_index(pool.clone(), args).await?;
+
+    let mut item_ids: Vec<&i64> = index.gid_by_item.keys().collect();
+    item_ids.sort();
+    let mut items_without_file = 0;
+    for id in item_ids {
+        let files = index.files_for_item(*id);
+        if files.is_empty() {
+            items_without_file += 1;
+        }
+        if args.verbose {
+            println!("Item ID {}: {} file(s) {:?}", id, files.len(), files);
+        }
+    }
+
+    let mut file_names: Vec<&String> = index.message_id_by_file.keys().collect();
+    file_names.sort();
+    let mut files_without_item = 0;
+    for file in file_names {
+        let items = index.items_for_file(file);
+        if items.is_empty() {
+            files_without_item += 1;
+        }
+        if args.verbose {
+            println!("File {}: item ID(s) {:?}", file, items);
+        }
+    }
+
+    println!(
+        "{} mail files with a Message-ID, {} items with a gid.",
+        index.message_id_by_file.len(),
+        index.gid_by_item.len()
+    );
+    println!(
+        "{} items without a matching file, {} files without a matching item.",
+        items_without_file, files_without_item
+    );
+    Ok(())
+}
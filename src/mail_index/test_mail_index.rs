@@ -0,0 +1,175 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the Message-ID index.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. The `tests/fixtures/gid.sql` fixture
+/// sets `pimitemtable`.`gid` for the mails found in `tests/data`.
+///
+/// # Test Cases
+///
+/// - `test_read_message_id`: Verifies that Message-ID headers are found independent
+///   of the case of the header name.
+/// - `test_read_folded_message_id`: Verifies that a Message-ID folded onto the next
+///   line is joined.
+/// - `test_read_header_field`: Verifies case-insensitive lookup of a header field.
+/// - `test_build_mail_index`: Verifies the lookups item -> files and file -> items.
+/// - `test_mail_index_cache`: Verifies that the index cache is written and reused.
+/// - `test_index_cache_escaping`: Verifies that paths with tabs, newlines and
+///   backslashes survive the index cache.
+///
+mod tests {
+    use crate::header::MailHeader;
+    use crate::mail_index::{
+        build_mail_index, get_index_cache_path, load_index_cache, read_message_id,
+        save_index_cache, IndexCacheEntry,
+    };
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use anyhow::Result;
+    use sqlx::mysql::MySqlPool;
+    use std::collections::HashMap;
+
+    const FOLDED_MAIL: &str = "local_mail/.inbox.directory/temporary/new/1291727681.2020.4jNSG:2,S";
+    const MIXED_CASE_MAIL: &str =
+        "local_mail/.inbox.directory/to_be_filed/cur/1330783242.R2038.sirius:2,S";
+
+    #[test]
+    fn test_read_message_id() -> Result<()> {
+        let path = format!(
+            "{}/tests/data/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            MIXED_CASE_MAIL
+        );
+        let message_id = read_message_id(&path)?;
+        assert_eq!(
+            message_id,
+            Some(
+                "OFDCFBA1D1.99586397-ONC125782D.0034BE63-C125782D.0034DFDB@LocalDomain".to_string()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_folded_message_id() -> Result<()> {
+        let path = format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), FOLDED_MAIL);
+        let message_id = read_message_id(&path)?;
+        assert_eq!(
+            message_id,
+            Some(
+                "KL1PR03MB7765F01F4FF98062ADEA08E5F951A@KL1PR03MB7765.apcprd03.prod.outlook.com"
+                    .to_string()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_cache_escaping() -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let cache_file = format!("{}/message_ids.tsv", temp_dir);
+        let entry = IndexCacheEntry {
+            size: 1,
+            mtime: 2,
+            message_id: "a\tb@c".to_string(),
+        };
+        let entries: HashMap<String, IndexCacheEntry> = [
+            "cur/tab\there:2,S",
+            "cur/line\nbreak:2,S",
+            "cur/back\\slash\\t:2,S",
+        ]
+        .into_iter()
+        .map(|path| (path.to_string(), entry.clone()))
+        .collect();
+
+        save_index_cache(&cache_file, &entries)?;
+        assert_eq!(std::fs::read_to_string(&cache_file)?.lines().count(), 3);
+        assert_eq!(load_index_cache(&cache_file), entries);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_header_field() -> Result<()> {
+        let path = format!(
+            "{}/tests/data/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            MIXED_CASE_MAIL
+        );
+        assert_eq!(
+            MailHeader::read(&path)?.get("SUBJECT"),
+            Some("Info: Bin im Stress".to_string())
+        );
+        assert_eq!(MailHeader::read(&path)?.get("X-Not-Present"), None);
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql", "../../tests/fixtures/gid.sql"))]
+    async fn test_build_mail_index(pool: MySqlPool) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let index = build_mail_index(pool.clone(), &args).await?;
+
+        // All five mails in tests/data/local_mail carry a Message-ID
+        assert_eq!(index.message_id_by_file.len(), 5);
+        // Item 206 is stored with angle brackets in the gid column
+        let files = index.files_for_item(206);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("1291727681.2020.4jNSG:2,S"));
+        assert_eq!(index.items_for_file(&files[0]), vec![206]);
+        // Item 50643 only lives in the file_db_data cache
+        assert!(index.files_for_item(50643).is_empty());
+        // Item 50645 has no gid at all
+        assert!(index.files_for_item(50645).is_empty());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql", "../../tests/fixtures/gid.sql"))]
+    async fn test_mail_index_cache(pool: MySqlPool) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, true);
+        let cache_file = get_index_cache_path(&args)?;
+
+        build_mail_index(pool.clone(), &args).await?;
+        let mut entries = load_index_cache(&cache_file);
+        assert_eq!(entries.len(), 5);
+
+        // Tamper with a cached entry: an unchanged file must not be scanned again
+        let (path, entry) = entries
+            .iter_mut()
+            .find(|(path, _)| path.ends_with(FOLDED_MAIL.rsplit('/').next().unwrap()))
+            .unwrap();
+        let path = path.clone();
+        entry.message_id = "cached@example".to_string();
+        save_index_cache(&cache_file, &entries)?;
+
+        let index = build_mail_index(pool.clone(), &args).await?;
+        assert_eq!(
+            index.message_id_by_file.get(&path),
+            Some(&"cached@example".to_string())
+        );
+        assert!(index.files_for_item(206).is_empty());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -12,15 +12,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::cmdline::Command;
 use crate::connect::connect_to_database;
 use crate::process::execute::clean_up;
 use anyhow::Result;
 
+pub(crate) mod cache_gc;
+pub(crate) mod check;
 pub(crate) mod cmdline;
 pub(crate) mod connect;
+pub(crate) mod context;
+pub(crate) mod dedupe;
+pub(crate) mod duplicate_rows;
+pub(crate) mod flag_snapshot;
+pub(crate) mod folders;
+pub(crate) mod header;
+pub(crate) mod impact;
+pub(crate) mod mail_index;
 #[cfg(test)]
 pub(crate) mod mockup;
+pub(crate) mod orphans;
 pub(crate) mod process;
+pub(crate) mod reconcile;
+pub(crate) mod rules;
+pub(crate) mod special;
+pub(crate) mod tags;
+pub(crate) mod tmp_files;
 pub(crate) mod todoitems;
 
 #[tokio::main]
@@ -33,11 +50,74 @@ async fn main() -> Result<()> {
     // Connect to the database
     let pool: sqlx::Pool<sqlx::MySql> = connect_to_database(&args).await?;
 
-    // Fetch todo pim items and process them
-    process::process_todo_items(pool.clone(), &args).await?;
+    // Run the requested maintenance command or fetch todo pim items and process them
+    let needs_clean_up = match &args.command {
+        None => {
+            process::process_todo_items(pool.clone(), &args).await?;
+            true
+        }
+        Some(Command::Index) => {
+            mail_index::report_mail_index(pool.clone(), &args).await?;
+            false
+        }
+        Some(Command::Dedupe { quarantine_path }) => {
+            dedupe::dedupe_mails(pool.clone(), &args, quarantine_path).await?;
+            true
+        }
+        Some(Command::DuplicateRows) => {
+            duplicate_rows::repair_duplicate_rows(pool.clone(), &args).await?;
+            true
+        }
+        Some(Command::Orphans) => {
+            orphans::report_orphan_files(pool.clone(), &args).await?;
+            false
+        }
+        Some(Command::Check { fix }) => {
+            check::check_and_fix_maildirs(pool.clone(), &args, *fix).await?;
+            *fix
+        }
+        Some(Command::Tmp {
+            min_age_hours,
+            remove,
+        }) => {
+            tmp_files::clean_up_tmp_files(pool.clone(), &args, *min_age_hours, *remove).await?;
+            true
+        }
+        Some(Command::Folders) => {
+            folders::reconcile_folders(pool.clone(), &args).await?;
+            false
+        }
+        Some(Command::Reconcile { policy }) => {
+            reconcile::reconcile_flags(pool.clone(), &args, *policy).await?;
+            true
+        }
+        Some(Command::SnapshotFlags { snapshot_path }) => {
+            flag_snapshot::snapshot_flags(pool.clone(), &args, snapshot_path).await?;
+            false
+        }
+        Some(Command::RestoreFlags { snapshot_path }) => {
+            flag_snapshot::restore_flags(pool.clone(), &args, snapshot_path).await?;
+            true
+        }
+        Some(Command::CacheGc {
+            recovery_path,
+            min_age_hours,
+        }) => {
+            cache_gc::collect_cache_garbage(pool.clone(), &args, recovery_path, *min_age_hours)
+                .await?;
+            false
+        }
+        Some(Command::ReattachTags) => {
+            tags::reattach_tags(pool.clone(), &args).await?;
+            true
+        }
+    };
 
     // Explicit disconnect from the database
     pool.close().await;
+    if !needs_clean_up {
+        return Ok(());
+    }
 
     // Clean up operations
     if args.dry_run {
@@ -14,6 +14,7 @@
 
 use crate::cmdline::CliArgs;
 use anyhow::Result;
+use sqlx::{MySql, Pool};
 
 /// Sets up a temporary mail directory structure for testing purposes.
 ///
@@ -66,14 +67,68 @@ pub fn teardown_tmp_mail_dir(temp_dir: &str) -> Result<()> {
 ///
 /// # Returns
 ///
-/// Returns a `CliArgs` instance with maildir and cache paths configured to use
-/// subdirectories within the provided `temp_dir`.
+/// Returns a `CliArgs` instance with maildir, cache and index cache paths configured
+/// to use locations within the provided `temp_dir`.
 ///
 pub fn create_test_cli_args(temp_dir: &str, dry_run: bool) -> CliArgs {
     CliArgs {
         maildir_path: format!("{}/local_mail/", temp_dir),
         mail_cache_path: format!("{}/file_db_data/", temp_dir),
+        index_cache_path: format!("{}/message_ids.tsv", temp_dir),
+        tag_store_path: format!("{}/tags/", temp_dir),
         dry_run,
         ..Default::default()
     }
 }
+
+/// Makes a collection the Outbox and flags items in it as `$QUEUED`.
+///
+/// # Arguments
+///
+/// * `pool` - Database connection pool
+/// * `collection_id` - Collection to mark as the Outbox instead of `outbox`
+/// * `item_ids` - Items to flag as queued
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if a database update fails.
+///
+pub async fn queue_in_outbox(
+    pool: Pool<MySql>,
+    collection_id: i64,
+    item_ids: &[i64],
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE `collectionattributetable` SET `collectionId` = ?
+        WHERE `type` = 'SpecialCollectionAttribute' AND `value` = 'outbox'",
+    )
+    .bind(collection_id)
+    .execute(&pool)
+    .await?;
+    for id in item_ids {
+        sqlx::query("INSERT INTO `pimitemflagrelation` VALUES (?, 10)")
+            .bind(id)
+            .execute(&pool)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Checks whether a pim item is still in the database.
+///
+/// # Arguments
+///
+/// * `pool` - Database connection pool
+/// * `id` - Pim item id
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if the database query fails.
+///
+pub async fn item_present(pool: Pool<MySql>, id: i64) -> Result<bool> {
+    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM pimitemtable WHERE id = ?")
+        .bind(id)
+        .fetch_one(&pool)
+        .await?;
+    Ok(row.0 > 0)
+}
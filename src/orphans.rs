@@ -0,0 +1,215 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::check::{check_mail_name, Violation};
+use crate::cmdline::CliArgs;
+use crate::header::MailHeader;
+use crate::process::maildirs::fetch_full_paths;
+use crate::process::target_path::split_mail_name;
+use crate::todoitems::fetch_remote_ids_by_collection;
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::collections::HashSet;
+
+#[cfg(test)]
+pub(crate) mod test_orphans;
+
+/// A maildir file without a matching `pimitemtable` row.
+#[derive(Debug)]
+pub struct OrphanFile {
+    pub path: String,
+    pub collection_id: i64,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub subject: Option<String>,
+    pub date: Option<String>,
+    /// Reason why Akonadi's maildir resource will not import the file at all
+    pub ignored: Option<&'static str>,
+    /// Naming or placement problems of an otherwise importable file
+    pub problems: Vec<&'static str>,
+}
+
+/// Check whether Akonadi's maildir resource skips a directory entry.
+/// The resource only lists visible regular files with valid UTF-8 names.
+///
+/// # Arguments
+/// - `name`: File name as found on disk
+/// - `is_file`: Whether the entry is a regular file
+///
+/// Returns the reason for skipping the entry, if any
+///
+pub fn akonadi_ignores(name: &std::ffi::OsStr, is_file: bool) -> Option<&'static str> {
+    let Some(name) = name.to_str() else {
+        return Some("file name is not valid UTF-8");
+    };
+    if name.starts_with('.') {
+        return Some("hidden file name");
+    }
+    if !is_file {
+        return Some("not a regular file");
+    }
+    None
+}
+
+/// Check the name of a mail file against its maildir sub-directory.
+///
+/// # Arguments
+/// - `sub_dir`: `new` or `cur`
+/// - `name`: File name
+///
+/// Returns the list of naming and placement problems
+///
+pub fn check_placement(sub_dir: &str, name: &str) -> Vec<&'static str> {
+    // Flag letters take no part in the placement
+    check_mail_name(sub_dir, name, false)
+        .into_iter()
+        .filter(|v| {
+            matches!(
+                v,
+                Violation::UnknownInfo | Violation::InfoInNew | Violation::NoInfoInCur
+            )
+        })
+        .map(|v| v.description())
+        .collect()
+}
+
+/// Find all files in the `new` and `cur` directories of the local folders
+/// without a `pimitemtable` row in the same collection. Files are compared
+/// by the unique part of their name, such that a file only renamed by a
+/// flag change is not reported.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<Vec<OrphanFile>>`
+///
+pub async fn find_orphan_files(pool: Pool<MySql>, args: &CliArgs) -> Result<Vec<OrphanFile>> {
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let remote_ids = fetch_remote_ids_by_collection(pool.clone()).await?;
+    let mut collections: Vec<(&i64, &String)> = full_paths.iter().collect();
+    collections.sort_by(|a, b| a.1.cmp(b.1));
+
+    let mut orphans = Vec::new();
+    for (collection_id, path) in collections {
+        let known: HashSet<&str> = remote_ids
+            .get(collection_id)
+            .map(|ids| ids.iter().map(|rid| split_mail_name(rid).0).collect())
+            .unwrap_or_default();
+        for sub_dir in ["new", "cur"] {
+            let Ok(entries) = std::fs::read_dir(format!("{}{}", path, sub_dir)) else {
+                continue;
+            };
+            let mut entries: Vec<std::fs::DirEntry> = entries.filter_map(|e| e.ok()).collect();
+            entries.sort_by_key(|e| e.file_name());
+            for entry in entries {
+                let file_name = entry.file_name();
+                let name = file_name.to_string_lossy();
+                if known.contains(split_mail_name(&name).0) {
+                    continue;
+                }
+                let is_file = entry.file_type().is_ok_and(|t| t.is_file());
+                let file_path = entry.path().to_string_lossy().to_string();
+                let ignored = akonadi_ignores(&file_name, is_file);
+                let mut problems = check_placement(sub_dir, &name);
+                let header = if is_file {
+                    if entry.metadata().is_ok_and(|m| m.len() == 0) {
+                        problems.push("empty file");
+                    }
+                    // Files may be moved or deleted by Akonadi or another client meanwhile
+                    match MailHeader::read(&file_path) {
+                        Ok(header) => header,
+                        Err(e) => {
+                            eprintln!("Skipping {}: {}", file_path, e);
+                            continue;
+                        }
+                    }
+                } else {
+                    MailHeader::default()
+                };
+                orphans.push(OrphanFile {
+                    path: file_path,
+                    collection_id: *collection_id,
+                    from: header.from(),
+                    to: header.to(),
+                    subject: header.subject(),
+                    date: header.get("date"),
+                    ignored,
+                    problems,
+                });
+            }
+        }
+    }
+    Ok(orphans)
+}
+
+/// Report all maildir files unknown to Akonadi, grouped by collection
+/// directory, together with their subject and date. Files Akonadi will not
+/// import at the next sync, or that are misnamed or misplaced, are marked.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<()>`
+///
+pub async fn report_orphan_files(pool: Pool<MySql>, args: &CliArgs) -> Result<()> {
+    let orphans = find_orphan_files(pool.clone(), args).await?;
+    let mut last_collection = None;
+    let mut ignored = 0;
+    let mut misplaced = 0;
+
+    for orphan in &orphans {
+        if last_collection != Some(orphan.collection_id) {
+            let folder = std::path::Path::new(&orphan.path)
+                .parent()
+                .and_then(|p| p.parent())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            println!(
+                "Files unknown to Akonadi in collection {} ({}):",
+                orphan.collection_id, folder
+            );
+            last_collection = Some(orphan.collection_id);
+        }
+        println!("  {}", orphan.path);
+        println!(
+            "    From: {}, To: {}",
+            orphan.from.as_deref().unwrap_or("-"),
+            orphan.to.as_deref().unwrap_or("-")
+        );
+        println!(
+            "    Date: {}, Subject: {}",
+            orphan.date.as_deref().unwrap_or("-"),
+            orphan.subject.as_deref().unwrap_or("-")
+        );
+        if let Some(reason) = orphan.ignored {
+            println!("    ignored by Akonadi: {}", reason);
+            ignored += 1;
+        } else if !orphan.problems.is_empty() {
+            misplaced += 1;
+        }
+        for problem in &orphan.problems {
+            println!("    problem: {}", problem);
+        }
+    }
+
+    println!(
+        "{} files unknown to Akonadi, {} will be ignored by Akonadi, {} are misnamed or misplaced.",
+        orphans.len(),
+        ignored,
+        misplaced
+    );
+    Ok(())
+}
@@ -0,0 +1,102 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the detection of maildir files unknown to Akonadi.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. Three files without a
+/// `pimitemtable` row are added: a correctly placed copy in `nirwana/cur`, a file
+/// without info suffix in `to_be_filed/cur` and a hidden file in `to_be_filed/new`.
+///
+/// # Test Cases
+///
+/// - `test_check_placement`: Verifies the naming and placement checks.
+/// - `test_akonadi_ignores`: Verifies the names skipped by Akonadi's maildir resource.
+/// - `test_find_orphan_files`: Verifies that only the added files are reported,
+///   with subject, date and their problems.
+///
+mod tests {
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use crate::orphans::{akonadi_ignores, check_placement, find_orphan_files};
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::ffi::OsStr;
+
+    const ORIGINAL: &str =
+        "local_mail/.inbox.directory/to_be_filed/cur/1330783242.R2038.sirius:2,S";
+    const ORPHAN_OK: &str = "local_mail/.inbox.directory/nirwana/cur/1400000000.R1.sirius:2,S";
+    const ORPHAN_NO_INFO: &str = "local_mail/.inbox.directory/to_be_filed/cur/1400000001.R2.sirius";
+    const ORPHAN_HIDDEN: &str = "local_mail/.inbox.directory/to_be_filed/new/.1400000002.R3.sirius";
+
+    #[test]
+    fn test_check_placement() {
+        assert!(check_placement("cur", "1400000000.R1.sirius:2,S").is_empty());
+        assert!(check_placement("new", "1400000000.R1.sirius").is_empty());
+        assert_eq!(check_placement("new", "1400000000.R1.sirius:2,S").len(), 1);
+        assert_eq!(check_placement("cur", "1400000000.R1.sirius").len(), 1);
+        assert_eq!(check_placement("cur", "1400000000.R1.sirius:1,S").len(), 2);
+    }
+
+    #[test]
+    fn test_akonadi_ignores() {
+        assert_eq!(
+            akonadi_ignores(OsStr::new("1400000000.R1.sirius"), true),
+            None
+        );
+        assert!(akonadi_ignores(OsStr::new(".1400000000.R1.sirius"), true).is_some());
+        assert!(akonadi_ignores(OsStr::new("1400000000.R1.sirius"), false).is_some());
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_find_orphan_files(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        for orphan in [ORPHAN_OK, ORPHAN_NO_INFO, ORPHAN_HIDDEN] {
+            let target = format!("{}/{}", temp_dir, orphan);
+            std::fs::create_dir_all(std::path::Path::new(&target).parent().unwrap())?;
+            std::fs::copy(format!("{}/{}", temp_dir, ORIGINAL), &target)?;
+        }
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let orphans = find_orphan_files(pool.clone(), &args).await?;
+        assert_eq!(orphans.len(), 3);
+
+        let ok = orphans
+            .iter()
+            .find(|o| o.path.ends_with(ORPHAN_OK))
+            .unwrap();
+        assert_eq!(ok.subject.as_deref(), Some("Info: Bin im Stress"));
+        assert_eq!(ok.date.as_deref(), Some("Fri, 04 Feb 2011 10:37:31 +0100"));
+        assert!(ok.ignored.is_none());
+        assert!(ok.problems.is_empty());
+
+        let no_info = orphans
+            .iter()
+            .find(|o| o.path.ends_with(ORPHAN_NO_INFO))
+            .unwrap();
+        assert!(no_info.ignored.is_none());
+        assert_eq!(no_info.problems.len(), 1);
+
+        let hidden = orphans
+            .iter()
+            .find(|o| o.path.ends_with(ORPHAN_HIDDEN))
+            .unwrap();
+        assert!(hidden.ignored.is_some());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -12,27 +12,57 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::cmdline::CliArgs;
-use crate::process::execute::{delete_file, move_file, update_akonadi_db};
+use crate::cmdline::{CliArgs, TrashPolicy};
+use crate::context::RunContext;
+use crate::header::MailHeader;
+use crate::impact::{report_delete_impact, report_delete_totals, DeleteImpact};
+use crate::process::execute::{delete_file, move_mail, update_akonadi_db, update_item_in_place};
 use crate::process::maildirs::fetch_full_paths;
 use crate::process::source_path::get_source_file_name;
-use crate::process::target_path::get_target_file_name;
+use crate::process::target_path::{fetch_flag_names, get_target_file_name};
+use crate::special::{
+    gains_draft_flag, get_trash_collection, is_queued, is_trash_item, report_special_totals,
+    SpecialTotals,
+};
+use crate::todoitems::selection::describe_selection;
 use crate::todoitems::{fetch_todo_pim_items, TodoPimItem};
 use anyhow::Result;
 use sqlx::{MySql, Pool};
 use std::collections::HashMap;
 
 pub(crate) mod execute;
+pub(crate) mod keywords;
 pub(crate) mod maildirs;
 pub(crate) mod source_path;
 pub(crate) mod target_path;
 #[cfg(test)]
+pub(crate) mod test_execute;
+#[cfg(test)]
+pub(crate) mod test_keywords;
+#[cfg(test)]
 pub(crate) mod test_single_todo;
 #[cfg(test)]
 pub(crate) mod test_todo_loop;
 
+/// What processing todo items did besides moving their files.
+#[derive(Debug, Default)]
+pub struct TodoTotals {
+    /// What deleting items cascaded away
+    pub deleted: DeleteImpact,
+    /// Items handled by a special-folder behaviour
+    pub special: SpecialTotals,
+}
+
+impl TodoTotals {
+    pub fn add(&mut self, other: &TodoTotals) {
+        self.deleted.add(&other.deleted);
+        self.special.add(&other.special);
+    }
+}
+
 /// Process todo pim items: move files and update akonadi db
 /// - Fetch mail directory tree with full paths
+/// - Load the folder rules and the special folders
 /// - Get todo pim items
 /// - Process each todo item by calling process_single_todo_item()
 ///
@@ -46,36 +76,53 @@ pub async fn process_todo_items(pool: Pool<MySql>, args: &CliArgs) -> Result<()>
     // Fetch mail directory tree with full paths
     let full_paths = fetch_full_paths(pool.clone(), args).await?;
 
+    let ctx = RunContext::load(pool.clone(), args).await?;
+    if args.dry_run || args.verbose {
+        if let Some(selection) = describe_selection(args, &ctx) {
+            println!("{}", selection);
+        }
+    }
+
     // get todo pim items
-    let todo_items: Vec<TodoPimItem> = fetch_todo_pim_items(pool.clone(), args).await?;
+    let todo_items: Vec<TodoPimItem> = fetch_todo_pim_items(pool.clone(), args, &ctx).await?;
 
+    let mut totals = TodoTotals::default();
     for item in todo_items {
-        process_single_todo_item(pool.clone(), &item, &full_paths, args).await?;
+        totals.add(&process_single_todo_item(pool.clone(), &item, &full_paths, args, &ctx).await?);
     }
+    report_special_totals(args, &totals.special);
+    report_delete_totals(args, &totals.deleted);
 
     Ok(())
 }
 
 /// Process a single todo pim item: move file and update akonadi db
+/// - Leave queued items in the Outbox alone, KMail has yet to send them
 /// - Get source file name
 /// - If source file does not exist, remove item from database
+/// - Move or purge items flagged `\DELETED` as `--trash` demands
 /// - Get target file name
-/// - If source and target are different, move file and update database
+/// - If source and target are different, move file and update database:
+///   with `--in-place`, items whose file is on disk keep their row with the
+///   new remote ID, all other items are removed for Akonadi to re-import them
 ///
 /// # Arguments
 /// - `pool`: Database connection pool
 /// - `item`: Reference to TodoPimItem
 /// - `full_paths`: Reference to HashMap of full paths
 /// - `args`: Command line arguments
+/// - `ctx`: Folder rules and special folders of the run
 ///
-/// Returns `Result<()>`
+/// Returns `Result<TodoTotals>`
 ///
 pub(crate) async fn process_single_todo_item(
     pool: Pool<MySql>,
     item: &TodoPimItem,
     full_paths: &HashMap<i64, String>,
     args: &CliArgs,
-) -> Result<()> {
+    ctx: &RunContext,
+) -> Result<TodoTotals> {
+    let mut totals = TodoTotals::default();
     let dry_run_msg_start = if args.dry_run {
         "Dry run"
     } else {
@@ -90,6 +137,13 @@ pub(crate) async fn process_single_todo_item(
         );
     }
 
+    let flags = fetch_flag_names(item.id, pool.clone()).await?;
+    if is_queued(&ctx.special_collections, item.collection_id, &flags) {
+        println!("Leaving queued item ID {} in the outbox.", item.id);
+        totals.special.queued += 1;
+        return Ok(totals);
+    }
+
     let source = get_source_file_name(pool.clone(), item, full_paths, args).await?;
     if source.is_none() {
         if args.verbose || args.dry_run {
@@ -99,25 +153,80 @@ pub(crate) async fn process_single_todo_item(
             );
         }
         if !args.dry_run {
-            update_akonadi_db(pool.clone(), item.id).await?;
+            totals
+                .deleted
+                .add(&update_akonadi_db(pool.clone(), args, item.id, None).await?);
+        } else {
+            totals
+                .deleted
+                .add(&report_delete_impact(pool.clone(), args, item.id).await?);
         }
-        return Ok(());
+        return Ok(totals);
     }
     let source = source.as_ref().unwrap();
-    let target = get_target_file_name(pool.clone(), item, full_paths, source).await?;
+    if args.verbose || args.dry_run {
+        // The summary is informational only, an unreadable file is handled below
+        let summary = match MailHeader::read(source) {
+            Ok(header) => header.summary(),
+            Err(e) => format!("header not readable: {}", e),
+        };
+        println!("{} item ID {}: {}", dry_run_msg_start, item.id, summary);
+    }
+    if is_trash_item(
+        args.trash,
+        &ctx.special_collections,
+        item.collection_id,
+        &flags,
+    ) {
+        return process_trash_item(pool, item, full_paths, source, args, ctx).await;
+    }
+    let target = get_target_file_name(pool.clone(), item, full_paths, source, args, ctx).await?;
     if source != &target {
+        if gains_draft_flag(
+            &ctx.special_collections,
+            item.collection_id,
+            source,
+            &target,
+        ) {
+            totals.special.drafts += 1;
+        }
         if args.verbose || args.dry_run {
             println!(
                 "{} item ID {}: {} {} to {}",
                 dry_run_msg_start, item.id, dry_run_msg_would, source, target
             );
         }
+        // Cache-only items have no file Akonadi could be pointed to
+        let in_place = args.in_place && item.remote_id.is_some();
+        if in_place && (args.verbose || args.dry_run) {
+            println!(
+                "{} item ID {}: updating remote ID in place",
+                dry_run_msg_start, item.id
+            );
+        }
         if !args.dry_run {
-            move_file(source, &target)?;
-            update_akonadi_db(pool.clone(), item.id).await?;
-        } else if source.contains("tmp_db_") {
-            // In dry run mode, clean up temporary cached files
-            delete_file(source)?;
+            if let Some(moved) = move_mail(pool.clone(), args, source, &target).await? {
+                if in_place {
+                    update_item_in_place(pool.clone(), item.id, &moved).await?;
+                } else {
+                    totals
+                        .deleted
+                        .add(&update_akonadi_db(pool.clone(), args, item.id, Some(&moved)).await?);
+                }
+            } else if source.contains("tmp_db_") {
+                // The item keeps its payload in the database
+                delete_file(source)?;
+            }
+        } else {
+            if !in_place {
+                totals
+                    .deleted
+                    .add(&report_delete_impact(pool.clone(), args, item.id).await?);
+            }
+            if source.contains("tmp_db_") {
+                // In dry run mode, clean up temporary cached files
+                delete_file(source)?;
+            }
         }
     } else if args.verbose || args.dry_run {
         println!(
@@ -125,5 +234,104 @@ pub(crate) async fn process_single_todo_item(
             dry_run_msg_start, item.id
         );
     }
-    Ok(())
+    Ok(totals)
+}
+
+/// Move an item flagged `\DELETED` to the local Trash folder or purge it,
+/// depending on `--trash`. Either way its row is deleted; a moved mail is
+/// re-imported by Akonadi in the Trash folder.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `item`: Reference to TodoPimItem
+/// - `full_paths`: Reference to HashMap of full paths
+/// - `source`: Current path of the mail file
+/// - `args`: Command line arguments
+/// - `ctx`: Folder rules and special folders of the run
+///
+/// Returns `Result<TodoTotals>`
+///
+async fn process_trash_item(
+    pool: Pool<MySql>,
+    item: &TodoPimItem,
+    full_paths: &HashMap<i64, String>,
+    source: &String,
+    args: &CliArgs,
+    ctx: &RunContext,
+) -> Result<TodoTotals> {
+    let mut totals = TodoTotals::default();
+    let dry_run_msg_start = if args.dry_run {
+        "Dry run"
+    } else {
+        "Processing"
+    };
+    if args.trash == TrashPolicy::Purge {
+        println!(
+            "{} item ID {}: {} deleted mail {}",
+            dry_run_msg_start,
+            item.id,
+            if args.dry_run {
+                "would purge"
+            } else {
+                "purging"
+            },
+            source
+        );
+        if args.dry_run {
+            totals
+                .deleted
+                .add(&report_delete_impact(pool.clone(), args, item.id).await?);
+            if source.contains("tmp_db_") {
+                delete_file(source)?;
+            }
+        } else {
+            // Delete the row first, a failure leaves the mail on disk
+            totals
+                .deleted
+                .add(&update_akonadi_db(pool.clone(), args, item.id, None).await?);
+            delete_file(source)?;
+        }
+        totals.special.trash_purged += 1;
+        return Ok(totals);
+    }
+
+    let Some(trash_id) = get_trash_collection(&ctx.special_collections) else {
+        anyhow::bail!("No local trash folder to move item ID {} to", item.id);
+    };
+    let trash_item = TodoPimItem {
+        id: item.id,
+        remote_id: item.remote_id.clone(),
+        collection_id: trash_id,
+    };
+    let target =
+        get_target_file_name(pool.clone(), &trash_item, full_paths, source, args, ctx).await?;
+    println!(
+        "{} item ID {}: {} deleted mail {} to {}",
+        dry_run_msg_start,
+        item.id,
+        if args.dry_run { "would move" } else { "moving" },
+        source,
+        target
+    );
+    let moved = if args.dry_run {
+        totals
+            .deleted
+            .add(&report_delete_impact(pool.clone(), args, item.id).await?);
+        true
+    } else if let Some(moved) = move_mail(pool.clone(), args, source, &target).await? {
+        totals
+            .deleted
+            .add(&update_akonadi_db(pool.clone(), args, item.id, Some(&moved)).await?);
+        true
+    } else {
+        false
+    };
+    // A temporary copy left in place goes only after the database is done with the item
+    if (args.dry_run || !moved) && source.contains("tmp_db_") {
+        delete_file(source)?;
+    }
+    if moved {
+        totals.special.trash_moved += 1;
+    }
+    Ok(totals)
 }
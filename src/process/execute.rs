@@ -12,10 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::cmdline::CliArgs;
+use crate::impact::{report_delete_impact, DeleteImpact};
+use crate::process::target_path::{create_new_mail_name, parse_mail_date};
+use crate::tags::record_item_tags;
 use anyhow::Result;
-use std::fs;
+use sha2::{Digest, Sha256};
+use sqlx::{MySql, Pool};
+use std::fs::{self, FileTimes};
+use std::io::Write;
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+use uuid::Uuid;
 
 pub fn ensure_writable_directory(dir: String) -> Result<()> {
     let path = Path::new(&dir);
@@ -41,7 +50,29 @@ pub fn ensure_writable_directory(dir: String) -> Result<()> {
     Ok(())
 }
 
-pub fn move_file(source: &str, target: &str) -> Result<()> {
+/// Result of a no-clobber move.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveOutcome {
+    /// The source was moved to the target
+    Moved,
+    /// The target already holds identical content, the source was removed
+    Duplicate,
+    /// The target already exists with different content, nothing was changed
+    Collision,
+}
+
+/// Move a file without ever replacing an existing target. The target is
+/// hard linked to the source before the source is unlinked, such that the
+/// link fails instead of overwriting an existing file. The directories are
+/// synced to disk, such that the move is durable once this returns.
+///
+/// # Arguments
+/// - `source`: File to move
+/// - `target`: New path of the file
+///
+/// Returns `Result<MoveOutcome>`
+///
+pub fn move_file(source: &str, target: &str) -> Result<MoveOutcome> {
     // Ensure both, source and target directory are writable
     if let Some(parent) = std::path::Path::new(&source).parent() {
         ensure_writable_directory(parent.to_string_lossy().to_string())?;
@@ -49,21 +80,326 @@ pub fn move_file(source: &str, target: &str) -> Result<()> {
     if let Some(parent) = std::path::Path::new(&target).parent() {
         ensure_writable_directory(parent.to_string_lossy().to_string())?;
     }
+    if source == target {
+        return Ok(MoveOutcome::Moved);
+    }
     // Move the file
-    std::fs::rename(source, target)?;
+    match fs::hard_link(source, target) {
+        Ok(()) => {
+            // Make the new link durable before the source disappears
+            sync_parent_directory(target)?;
+            fs::remove_file(source)?;
+            sync_parent_directory(source)?;
+            Ok(MoveOutcome::Moved)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => resolve_existing(source, target),
+        // Source and target live on different filesystems, e.g. the cache root
+        // below ~/.local/share and the maildir
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => copy_file(source, target),
+        Err(e) => Err(anyhow::anyhow!(
+            "Cannot move {} to {}: {}",
+            source,
+            target,
+            e
+        )),
+    }
+}
+
+/// Drop the source if an existing target holds identical content.
+fn resolve_existing(source: &str, target: &str) -> Result<MoveOutcome> {
+    if files_identical(source, target)? {
+        fs::remove_file(source)?;
+        Ok(MoveOutcome::Duplicate)
+    } else {
+        Ok(MoveOutcome::Collision)
+    }
+}
+
+/// Move a file across filesystems without replacing an existing target.
+/// Following the maildir protocol, the copy is written into the `tmp`
+/// directory of the target folder, synced to disk and verified by size and
+/// hash, before it is linked into place and the source is unlinked. A failed
+/// copy is removed and reported as error, leaving the source in place.
+///
+/// # Arguments
+/// - `source`: File to move
+/// - `target`: New path of the file
+///
+/// Returns `Result<MoveOutcome>`
+///
+pub fn copy_file(source: &str, target: &str) -> Result<MoveOutcome> {
+    if Path::new(target).exists() {
+        return resolve_existing(source, target);
+    }
+    let staging = get_staging_path(target)?;
+    let mut input =
+        fs::File::open(source).map_err(|e| anyhow::anyhow!("Cannot read {}: {}", source, e))?;
+    let mut output = match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&staging)
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return resolve_existing(source, target);
+        }
+        Err(e) => return Err(anyhow::anyhow!("Cannot create {}: {}", staging, e)),
+    };
+    let copied = std::io::copy(&mut input, &mut output).and_then(|_| output.sync_all());
+    let verified = copied.is_ok()
+        && fs::metadata(source)?.len() == fs::metadata(&staging)?.len()
+        && file_digest(source)? == file_digest(&staging)?;
+    if !verified {
+        fs::remove_file(&staging)?;
+        return Err(anyhow::anyhow!(
+            "Cannot copy {} to {}: {}",
+            source,
+            target,
+            copied
+                .err()
+                .map_or("copy differs from source".to_string(), |e| e.to_string())
+        ));
+    }
+    if staging != target {
+        let linked = fs::hard_link(&staging, target);
+        fs::remove_file(&staging)?;
+        match linked {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                return resolve_existing(source, target);
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "Cannot move {} to {}: {}",
+                    source,
+                    target,
+                    e
+                ))
+            }
+        }
+    }
+    sync_parent_directory(target)?;
+    fs::remove_file(source)?;
+    sync_parent_directory(source)?;
+    Ok(MoveOutcome::Moved)
+}
+
+/// Path in the `tmp` directory of a maildir folder to write a new file to
+/// before it is delivered into `new` or `cur`. Targets outside of `new` and
+/// `cur` are written directly.
+///
+/// # Arguments
+/// - `target`: Final path of the file
+///
+/// Returns `Result<String>`
+///
+pub fn get_staging_path(target: &str) -> Result<String> {
+    let sub_dir = Path::new(target)
+        .parent()
+        .filter(|p| p.file_name().is_some_and(|n| n == "new" || n == "cur"));
+    let Some(folder) = sub_dir.and_then(|p| p.parent()) else {
+        return Ok(target.to_string());
+    };
+    let tmp_dir = format!("{}/tmp", folder.to_string_lossy());
+    ensure_writable_directory(tmp_dir.clone())?;
+    Ok(format!("{}/{}", tmp_dir, Uuid::new_v4()))
+}
+
+/// Write data into a new file and sync the file and its directory to disk.
+///
+/// # Arguments
+/// - `path`: Path of the new file, which must not exist yet
+/// - `data`: Contents of the file
+///
+/// Returns `Result<()>`
+///
+pub fn write_file_durably(path: &str, data: &[u8]) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", path, e))?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    sync_parent_directory(path)?;
     Ok(())
 }
 
+/// Flush the directory entry of a file to disk.
+pub fn sync_parent_directory(path: &str) -> std::io::Result<()> {
+    match Path::new(path).parent() {
+        Some(parent) => fs::File::open(parent)?.sync_all(),
+        None => Ok(()),
+    }
+}
+
+/// SHA-256 hash of the contents of a file.
+pub fn file_digest(path: &str) -> Result<String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Compare the contents of two files.
+pub fn files_identical(first: &str, second: &str) -> Result<bool> {
+    if fs::metadata(first)?.len() != fs::metadata(second)?.len() {
+        return Ok(false);
+    }
+    Ok(fs::read(first)? == fs::read(second)?)
+}
+
+/// Move a mail file without replacing an existing target. If the target
+/// exists with different content and lives in a maildir folder, the mail is
+/// moved under a new unique name with the same info suffix instead.
+///
+/// With `--set-mtime`, the file times of the moved mail are set from its
+/// Date header.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `source`: Mail file to move
+/// - `target`: New path of the mail file
+///
+/// Returns the final path of the mail, or `None` if it was left in place
+///
+pub async fn move_mail(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    source: &str,
+    target: &str,
+) -> Result<Option<String>> {
+    let moved = move_mail_no_clobber(pool, source, target).await?;
+    if let Some(path) = moved.as_ref() {
+        if args.set_mtime {
+            set_mail_times(path)?;
+        }
+    }
+    Ok(moved)
+}
+
+/// Move a mail file, falling back to a new unique name on a collision.
+async fn move_mail_no_clobber(
+    pool: Pool<MySql>,
+    source: &str,
+    target: &str,
+) -> Result<Option<String>> {
+    if move_file(source, target)? != MoveOutcome::Collision {
+        return Ok(Some(target.to_string()));
+    }
+    let target_path = Path::new(target);
+    let sub_dir = target_path
+        .parent()
+        .filter(|p| p.file_name().is_some_and(|n| n == "new" || n == "cur"));
+    let folder = sub_dir.and_then(|p| p.parent());
+    if let (Some(folder), Some(sub_dir), Some(name)) = (folder, sub_dir, target_path.file_name()) {
+        let folder = format!("{}/", folder.to_string_lossy());
+        let sub_dir = sub_dir.file_name().unwrap_or_default().to_string_lossy();
+        let name = name.to_string_lossy();
+        let info = name.find(':').map(|pos| &name[pos..]).unwrap_or("");
+        let mail_name =
+            create_new_mail_name(pool.clone(), &source.to_string(), &folder, None).await?;
+        let new_target = format!("{}{}/{}{}", folder, sub_dir, mail_name, info);
+        println!(
+            "Target {} exists with different content, using {}",
+            target, new_target
+        );
+        if move_file(source, &new_target)? != MoveOutcome::Collision {
+            return Ok(Some(new_target));
+        }
+    }
+    println!(
+        "Skipping: {} exists with different content, {} is left in place",
+        target, source
+    );
+    Ok(None)
+}
+
+/// Set the access and modification time of a mail file to the time stamp
+/// of its Date header. Files whose modification time already matches are
+/// left alone, as are files without a parseable Date header.
+///
+/// # Arguments
+/// - `path`: Mail file
+///
+/// Returns whether the file times were changed
+///
+pub fn set_mail_times(path: &str) -> Result<bool> {
+    let Some(date) = parse_mail_date(path)? else {
+        return Ok(false);
+    };
+    let mtime = fs::metadata(path)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    if mtime == date {
+        return Ok(false);
+    }
+    let time = UNIX_EPOCH + Duration::from_secs(date);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_times(FileTimes::new().set_accessed(time).set_modified(time)))
+        .map_err(|e| anyhow::anyhow!("Cannot set file times of {}: {}", path, e))?;
+    Ok(true)
+}
+
 pub fn delete_file(path: &str) -> Result<()> {
     std::fs::remove_file(path)?;
     Ok(())
 }
 
-pub async fn update_akonadi_db(pool: sqlx::Pool<sqlx::MySql>, id: i64) -> Result<()> {
+/// Delete an item from the Akonadi database, such that Akonadi re-imports
+/// its file. What the delete cascades away is reported and the tags of the
+/// item are recorded beforehand, see `report_delete_impact` and `record_item_tags`.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `id`: Pim item id
+/// - `mail_file`: File holding the mail after the move, if any
+///
+/// Returns `Result<DeleteImpact>` with what the delete cascaded away
+///
+pub async fn update_akonadi_db(
+    pool: sqlx::Pool<sqlx::MySql>,
+    args: &CliArgs,
+    id: i64,
+    mail_file: Option<&str>,
+) -> Result<DeleteImpact> {
+    let impact = report_delete_impact(pool.clone(), args, id).await?;
+    record_item_tags(pool.clone(), args, id, mail_file).await?;
     sqlx::query("DELETE FROM pimitemtable WHERE id = ?")
         .bind(id)
         .execute(&pool)
         .await?;
+    Ok(impact)
+}
+
+/// Point an item to the new name of its file instead of deleting it, which
+/// keeps everything that only lives in Akonadi: annotations, attribute
+/// parts, relations and the item id itself. Only valid for items whose
+/// file is on disk and was merely renamed or moved between `new` and `cur`.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `id`: Pim item id
+/// - `mail_file`: New path of the mail file
+///
+/// Returns `Result<()>`
+///
+pub async fn update_item_in_place(pool: Pool<MySql>, id: i64, mail_file: &str) -> Result<()> {
+    let remote_id = mail_file.rsplit('/').next().unwrap_or(mail_file);
+    sqlx::query(
+        "UPDATE `pimitemtable` SET `remoteId` = ?, `dirty` = 0, `rev` = `rev` + 1
+        WHERE `id` = ?",
+    )
+    .bind(remote_id)
+    .bind(id)
+    .execute(&pool)
+    .await?;
     Ok(())
 }
 
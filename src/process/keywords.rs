@@ -0,0 +1,114 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use std::path::Path;
+
+/// Number of keyword letters `a`-`z`.
+const KEYWORD_SLOTS: usize = 26;
+
+/// Dovecot keyword letters of a maildir folder. The file `dovecot-keywords`
+/// in the folder holds lines `<index> <keyword>`, where index 0 stands for
+/// the letter `a` in the info suffix of a file name, 1 for `b`, and so on.
+#[derive(Debug)]
+pub struct DovecotKeywords {
+    path: String,
+    keywords: Vec<Option<String>>,
+    changed: bool,
+}
+
+impl DovecotKeywords {
+    /// Load the keywords of a folder. A missing file yields no keywords.
+    pub fn load(folder: &str) -> Result<DovecotKeywords> {
+        let path = Path::new(folder)
+            .join("dovecot-keywords")
+            .to_string_lossy()
+            .to_string();
+        let mut keywords = vec![None; KEYWORD_SLOTS];
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            for line in content.lines() {
+                let Some((index, keyword)) = line.split_once(' ') else {
+                    continue;
+                };
+                if let Some(slot) = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| keywords.get_mut(index))
+                {
+                    *slot = Some(keyword.trim().to_string());
+                }
+            }
+        }
+        Ok(DovecotKeywords {
+            path,
+            keywords,
+            changed: false,
+        })
+    }
+
+    /// Letter of a keyword, matched case-insensitively. Unknown keywords
+    /// get the first free letter.
+    ///
+    /// Returns `None` if all letters are taken
+    ///
+    pub fn letter_for(&mut self, keyword: &str) -> Option<char> {
+        let index = match self
+            .keywords
+            .iter()
+            .position(|k| k.as_ref().is_some_and(|k| k.eq_ignore_ascii_case(keyword)))
+        {
+            Some(index) => index,
+            None => {
+                let index = self.keywords.iter().position(|k| k.is_none())?;
+                self.keywords[index] = Some(keyword.to_string());
+                self.changed = true;
+                index
+            }
+        };
+        Some((b'a' + index as u8) as char)
+    }
+
+    /// Keyword of a letter `a`-`z`.
+    ///
+    /// Returns `None` if the letter is not assigned
+    ///
+    pub fn keyword_for(&self, letter: char) -> Option<&str> {
+        if !letter.is_ascii_lowercase() {
+            return None;
+        }
+        self.keywords
+            .get((letter as u8 - b'a') as usize)
+            .and_then(|k| k.as_deref())
+    }
+
+    /// Write the keywords file if keywords were added.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        let content: String = self
+            .keywords
+            .iter()
+            .enumerate()
+            .filter_map(|(index, k)| k.as_ref().map(|k| format!("{} {}\n", index, k)))
+            .collect();
+        // Replace the file atomically, Dovecot may read it concurrently
+        let tmp_path = format!("{}.tmp", self.path);
+        std::fs::write(&tmp_path, content)
+            .and_then(|_| std::fs::rename(&tmp_path, &self.path))
+            .map_err(|e| anyhow::anyhow!("Cannot write {}: {}", self.path, e))?;
+        self.changed = false;
+        Ok(())
+    }
+}
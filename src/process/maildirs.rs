@@ -159,3 +159,14 @@ pub async fn fetch_full_paths(pool: Pool<MySql>, args: &CliArgs) -> Result<HashM
         .collect::<anyhow::Result<HashMap<i64, String>>>()?;
     Ok(result)
 }
+
+/// Find the collection a maildir file belongs to. The file is expected in the
+/// `new`, `cur` or `tmp` sub-directory of one of the collection paths.
+pub fn find_collection_id(full_paths: &HashMap<i64, String>, mail_file: &str) -> Option<i64> {
+    let folder = std::path::Path::new(mail_file).parent()?.parent()?;
+    let folder = format!("{}/", folder.to_string_lossy());
+    full_paths
+        .iter()
+        .find(|(_, path)| **path == folder)
+        .map(|(id, _)| *id)
+}
@@ -13,11 +13,11 @@
 // limitations under the License.
 
 use crate::cmdline::CliArgs;
+use crate::process::execute::{ensure_writable_directory, write_file_durably};
 use crate::todoitems::TodoPimItem;
 use anyhow::Result;
 use sqlx::{MySql, Pool};
 use std::collections::HashMap;
-use std::io::Write;
 use uuid::Uuid;
 
 pub(crate) mod cache_root;
@@ -40,25 +40,52 @@ pub async fn get_source_file_name(
                 )
             })?
             .clone();
-        let pattern = format!("{}*/{}", &path, rid);
-        Ok(Some(get_single_matching_file(&pattern).await?))
+        let paths = get_maildir_files(&path, rid)?;
+        if paths.len() != 1 {
+            anyhow::bail!("Expected exactly one file, found {}", paths.len());
+        }
+        Ok(Some(paths[0].clone()))
     } else {
-        get_cached_email(item.id, pool, args).await
+        let folder = full_paths
+            .get(&item.collection_id)
+            .map(|path| path.as_str());
+        get_cached_email(item.id, pool, args, folder).await
     }
 }
 
 pub async fn get_single_matching_file(pattern: &str) -> Result<String> {
+    let paths = get_matching_files(pattern)?;
+
+    if paths.len() != 1 {
+        anyhow::bail!("Expected exactly one file, found {}", paths.len());
+    }
+
+    Ok(paths[0].clone())
+}
+
+pub fn get_matching_files(pattern: &str) -> Result<Vec<String>> {
     let mut paths = Vec::new();
 
     for entry in glob::glob(pattern)? {
-        paths.push(entry?);
+        paths.push(entry?.to_string_lossy().to_string());
     }
 
-    if paths.len() != 1 {
-        anyhow::bail!("Expected exactly one file, found {}", paths.len());
-    }
+    Ok(paths)
+}
 
-    Ok(paths[0].to_string_lossy().to_string())
+/// Find the files with the given name in the `new` and `cur` directories of
+/// a maildir folder. Files in `tmp` are still being delivered or are garbage
+/// and never count as the source of an item.
+pub fn get_maildir_files(path: &str, name: &str) -> Result<Vec<String>> {
+    Ok(get_matching_files(&format!("{}*/{}", path, name))?
+        .into_iter()
+        .filter(|file| {
+            std::path::Path::new(file)
+                .parent()
+                .and_then(|p| p.file_name())
+                .is_some_and(|sub_dir| sub_dir == "new" || sub_dir == "cur")
+        })
+        .collect())
 }
 
 pub fn get_cache_root_path(args: &CliArgs) -> Result<String> {
@@ -81,10 +108,26 @@ pub fn get_cache_root_path(args: &CliArgs) -> Result<String> {
     }
 }
 
+/// Get the path of the payload of an item without remote id.
+/// - Payloads stored in the file system are taken from the cache root.
+/// - Payloads stored in the database are written into a `tmp_db_` file in
+///   the `tmp` directory of the item's folder, or the cache root if the
+///   folder is unknown, and synced to disk. A dry run stages the file in
+///   the system's temporary directory instead, leaving the maildir alone.
+///
+/// # Arguments
+/// - `file_id`: Item id
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `folder`: Maildir folder of the item
+///
+/// Returns the path of the payload, or `None` if the item has no payload
+///
 pub async fn get_cached_email(
     file_id: i64,
     pool: Pool<MySql>,
     args: &CliArgs,
+    folder: Option<&str>,
 ) -> Result<Option<String>> {
     #[derive(sqlx::FromRow)]
     struct CachedEmail {
@@ -130,9 +173,20 @@ pub async fn get_cached_email(
         // Cached email is stored in database
         // Create a temporary file to store the cached email data
         // In case of dry-run, this file is later removed without further use
-        let unique_name = format!("{}tmp_db_{}", &cache_root_dir, Uuid::new_v4());
-        let mut file = std::fs::File::create(&unique_name)?;
-        file.write_all(&data)?;
+        let tmp_dir = match folder {
+            _ if args.dry_run => format!(
+                "{}/",
+                std::env::temp_dir().to_string_lossy().trim_end_matches('/')
+            ),
+            Some(folder) => {
+                let tmp_dir = format!("{}tmp/", folder);
+                ensure_writable_directory(tmp_dir.clone())?;
+                tmp_dir
+            }
+            None => cache_root_dir,
+        };
+        let unique_name = format!("{}tmp_db_{}", tmp_dir, Uuid::new_v4());
+        write_file_durably(&unique_name, &data)?;
         Ok(Some(unique_name))
     }
 }
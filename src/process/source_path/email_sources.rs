@@ -67,7 +67,7 @@ mod tests {
         // Test: Retrieve the cached email path for file_id 50638
         // The email with file_id 50638 has no remote_id and is cached in the file system
         let file_id = 50638;
-        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args).await?;
+        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args, None).await?;
         assert!(result.is_some());
         let result = result.unwrap();
         assert!(!result.is_empty());
@@ -91,7 +91,7 @@ mod tests {
         // Test: Retrieve the cached email path for file_id 50638
         // The email with file_id 50638 has no remote_id and is cached in the file system
         let file_id = 50638;
-        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args).await?;
+        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args, None).await?;
         assert!(result.is_some());
         let result = result.unwrap();
         assert!(!result.is_empty());
@@ -116,17 +116,18 @@ mod tests {
 
         // Test: Retrieve the cached email path for file_id 50645
         // The email with file_id 50645 has no remote_id and is cached in the database
-        // This should create a temporary file with the email contents
+        // This should create a temporary file outside the maildir and the cache
         let file_id = 50645;
-        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args).await?;
+        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args, None).await?;
         assert!(result.is_some());
         let result = result.unwrap();
         assert!(!result.is_empty());
         assert!(!result.contains("//"));
-        assert!(result.contains(&args.mail_cache_path));
+        assert!(!result.contains(&args.mail_cache_path));
+        assert!(!result.contains(&args.maildir_path));
         assert!(result.contains("tmp_db_"));
-        assert!(std::path::Path::new(&result).exists());
         assert!(std::path::Path::new(&result).is_file());
+        std::fs::remove_file(&result)?;
 
         // Clean up: Remove the temporary directory
         teardown_tmp_mail_dir(&temp_dir)?;
@@ -146,7 +147,7 @@ mod tests {
         // The email with file_id 50645 has no remote_id and is cached in the database
         // This should create a temporary file with the email contents
         let file_id = 50645;
-        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args).await?;
+        let result: Option<String> = get_cached_email(file_id, pool.clone(), &args, None).await?;
         assert!(result.is_some());
         let result = result.unwrap();
         assert!(!result.is_empty());
@@ -32,7 +32,7 @@ mod tests {
         // file_id 132632 exists in the test database without a cached email
         // Ensure that the function returns None
         let file_id = 132632;
-        let result = get_cached_email(file_id, pool, &args).await;
+        let result = get_cached_email(file_id, pool, &args, None).await;
 
         // Assert that the result is OK
         assert!(result.is_ok());
@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::todoitems::TodoPimItem;
+use crate::cmdline::CliArgs;
+use crate::context::RunContext;
+use crate::header::MailHeader;
+use crate::process::keywords::DovecotKeywords;
+use crate::special::apply_draft_flag;
+use crate::todoitems::{fetch_item_datetime, TodoPimItem};
 use anyhow::Result;
-use chrono::DateTime;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::sync::LazyLock;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use sqlx::mysql::MySql;
@@ -28,30 +31,43 @@ use regex::Regex;
 
 pub(crate) mod email_targets;
 
+/// Unique part `<secs>.R<rand>.<host>` or `<secs>.M<usec>P<pid>R<rand>.<host>`
+/// of a maildir file name.
+static UNIQUE_MAIL_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+\.(?:R\d+|M\d+P\d+R\d+)\.[^:/]+)").unwrap());
+
 pub async fn get_target_file_name(
     pool: Pool<MySql>,
     item: &TodoPimItem,
     full_paths: &HashMap<i64, String>,
     source: &String,
+    args: &CliArgs,
+    ctx: &RunContext,
 ) -> Result<String> {
-    let mail_name: String;
-    let re = Regex::new(r"(\d+\.R\d+\.\w+)").unwrap();
-    if let Some(rid) = item.remote_id.as_ref() {
-        if let Some(caps) = re.captures(rid) {
-            // Use existing mail name from remote ID
-            mail_name = caps.get(1).unwrap().as_str().to_string();
-        } else {
-            // Generate mail name based on timestamp, R value, and hostname
-            mail_name = create_new_mail_name(pool.clone(), source).await?;
-        }
-    } else {
-        // Generate mail name based on timestamp, R value, and hostname
-        mail_name = create_new_mail_name(pool.clone(), source).await?;
-    }
     // Get mail info (flags) from database
-    let mail_info = get_mail_info(item.id, pool).await?;
-    // Construct final target file name with path, cur/new prefix, mail name, and mail info
-    let cur_new_name = if mail_info.is_empty() { "new" } else { "cur" };
+    let folder = full_paths
+        .get(&item.collection_id)
+        .map(|path| path.as_str())
+        .unwrap_or_default();
+    let mail_info = get_mail_info(item.id, pool.clone(), args, folder).await?;
+    // Force or forbid flags as the folder rule demands
+    let mut letters = split_mail_name(&mail_info).1;
+    ctx.folder_rule(item.collection_id)
+        .apply_flags(&mut letters);
+    apply_draft_flag(&ctx.special_collections, item.collection_id, &mut letters);
+    let mail_info = build_mail_info(letters);
+    build_target_file_name(pool, item, full_paths, source, &mail_info).await
+}
+
+/// Compose the target file name of an item from its collection path,
+/// its mail name and the given mail info suffix `:2,<flags>`.
+pub async fn build_target_file_name(
+    pool: Pool<MySql>,
+    item: &TodoPimItem,
+    full_paths: &HashMap<i64, String>,
+    source: &String,
+    mail_info: &str,
+) -> Result<String> {
     let directory_path = full_paths
         .get(&item.collection_id)
         .ok_or_else(|| {
@@ -66,40 +82,141 @@ pub async fn get_target_file_name(
     } else {
         format!("{}/", directory_path)
     };
+    let mail_name = match item.remote_id.as_deref().and_then(get_unique_mail_name) {
+        // Use existing mail name from remote ID
+        Some(name) => name.to_string(),
+        // Generate mail name based on timestamp, process, random value, and hostname
+        None => create_new_mail_name(pool.clone(), source, &path, Some(item.id)).await?,
+    };
+    // Construct final target file name with path, cur/new prefix, mail name, and mail info
+    let cur_new_name = if mail_info.is_empty() { "new" } else { "cur" };
     Ok(format!(
         "{}{}/{}{}",
         path, cur_new_name, mail_name, mail_info
     ))
 }
 
-pub async fn create_new_mail_name(pool: Pool<MySql>, source: &String) -> Result<String> {
-    // Generate mail name based on timestamp, R value, and hostname
-    let mail_time_stamp = get_mail_time_stamp(source)?;
+/// Extract the unique part of a maildir file name generated by this tool or
+/// by KMail, i.e. `<secs>.R<rand>.<host>` or `<secs>.M<usec>P<pid>R<rand>.<host>`.
+/// Returns `None` for other names, which get a new name when moved.
+pub fn get_unique_mail_name(name: &str) -> Option<&str> {
+    UNIQUE_MAIL_NAME
+        .captures(name)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+}
+
+/// Sanitise a host name for use in a maildir file name as required by the
+/// maildir spec: `/` is replaced by `\057` and `:` by `\072`.
+pub fn sanitize_hostname(hostname: &str) -> String {
+    hostname.replace('/', "\\057").replace(':', "\\072")
+}
+
+/// Generate a maildir file name `<secs>.M<usec>P<pid>R<rand>.<host>` as
+/// recommended by the maildir spec. The seconds are taken from the Date
+/// header of the mail. The name is checked to be unused in the `new`, `cur`
+/// and `tmp` directories of the target folder and by any remote ID.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `source`: Mail file providing the time stamp
+/// - `folder`: Target maildir folder with trailing slash
+/// - `item_id`: Pim item of the mail, if known
+///
+/// Returns `Result<String>`
+///
+pub async fn create_new_mail_name(
+    pool: Pool<MySql>,
+    source: &String,
+    folder: &str,
+    item_id: Option<i64>,
+) -> Result<String> {
+    let mail_time_stamp = get_mail_time_stamp(pool.clone(), source, item_id).await?;
     let hostname = gethostname::gethostname()
         .into_string()
         .unwrap_or("unknownhost".to_string());
-    // Get R value from database
-    let r_value = get_r_value(pool.clone(), mail_time_stamp).await?;
-    // Construct mail name
-    Ok(format!("{}.R{}.{}", mail_time_stamp, r_value, hostname))
-}
-
-pub fn get_mail_time_stamp(mail_file: &String) -> Result<u64> {
-    // Open the mail file and read line by line to find the Date header
-    let file = File::open(mail_file)
-        .map_err(|e| anyhow::anyhow!("Cannot read mail file: {}: {}", mail_file, e))?;
-    let reader = BufReader::new(file);
-
-    for line in reader.lines().map_while(Result::ok) {
-        // Look for the line starting with "Date: "
-        if let Some(date_str) = line.strip_prefix("Date: ") {
-            // Parse the date string using RFC 2822 format
-            if let Ok(date_time) = DateTime::parse_from_rfc2822(date_str) {
-                // Return the timestamp as seconds since UNIX_EPOCH
-                return Ok(date_time.timestamp() as u64);
+    let hostname = sanitize_hostname(&hostname);
+    for _ in 0..100 {
+        let usec = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| anyhow::anyhow!("Time went backwards"))?
+            .subsec_micros();
+        let r_value: u32 = rand::rng().random();
+        let mail_name = format!(
+            "{}.M{}P{}R{}.{}",
+            mail_time_stamp,
+            usec,
+            std::process::id(),
+            r_value,
+            hostname
+        );
+        if !is_mail_name_in_use(pool.clone(), folder, &mail_name).await? {
+            return Ok(mail_name);
+        }
+    }
+    anyhow::bail!("Cannot find an unused mail name in {}", folder)
+}
+
+/// Check whether a unique mail name is used by a file in the `new`, `cur` or
+/// `tmp` directory of a folder, with any flags, or by the remote ID of a mail.
+pub async fn is_mail_name_in_use(pool: Pool<MySql>, folder: &str, mail_name: &str) -> Result<bool> {
+    for sub_dir in ["new", "cur", "tmp"] {
+        let Ok(entries) = std::fs::read_dir(format!("{}{}", folder, sub_dir)) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if split_mail_name(&entry.file_name().to_string_lossy()).0 == mail_name {
+                return Ok(true);
             }
         }
     }
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM `pimitemtable`
+         WHERE `mimeTypeId` = 2 AND CONVERT(`remoteId`, CHAR) LIKE CONCAT(?, '%')",
+    )
+    .bind(mail_name)
+    .fetch_one(&pool)
+    .await?;
+    Ok(count > 0)
+}
+
+/// Time stamp of a mail in seconds since UNIX_EPOCH, taken from the first
+/// available source:
+/// - the Date header,
+/// - the newest `Received:` header,
+/// - the `datetime` column of the pim item,
+/// - the modification time of the file.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `mail_file`: Mail file
+/// - `item_id`: Pim item of the mail, if known
+///
+/// Returns `Result<u64>`
+///
+pub async fn get_mail_time_stamp(
+    pool: Pool<MySql>,
+    mail_file: &String,
+    item_id: Option<i64>,
+) -> Result<u64> {
+    if let Some(time_stamp) = parse_mail_date(mail_file)? {
+        return Ok(time_stamp);
+    }
+    if let Some(time_stamp) = parse_received_date(mail_file)? {
+        return Ok(time_stamp);
+    }
+    if let Some(id) = item_id {
+        if let Some(time_stamp) = fetch_item_datetime(pool, id).await? {
+            return Ok(time_stamp);
+        }
+    }
+    if let Some(time_stamp) = std::fs::metadata(mail_file)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    {
+        return Ok(time_stamp.as_secs());
+    }
 
     /*
     If no date found or parsing failed, return current time in seconds since UNIX_EPOCH minus
@@ -109,6 +226,21 @@ pub fn get_mail_time_stamp(mail_file: &String) -> Result<u64> {
     Ok(get_time_now_secs()?.saturating_sub(random_offset))
 }
 
+/// Time stamp of the Date header of a mail in seconds since UNIX_EPOCH.
+pub fn parse_mail_date(mail_file: &str) -> Result<Option<u64>> {
+    Ok(MailHeader::read(mail_file)?
+        .date()
+        .and_then(|time_stamp| u64::try_from(time_stamp).ok()))
+}
+
+/// Time stamp of the newest `Received:` header of a mail in seconds since
+/// UNIX_EPOCH.
+pub fn parse_received_date(mail_file: &str) -> Result<Option<u64>> {
+    Ok(MailHeader::read(mail_file)?
+        .received_date()
+        .and_then(|time_stamp| u64::try_from(time_stamp).ok()))
+}
+
 pub fn get_time_now_secs() -> Result<u64> {
     // Get the current system time in seconds since UNIX_EPOCH
     Ok(SystemTime::now()
@@ -117,74 +249,203 @@ pub fn get_time_now_secs() -> Result<u64> {
         .as_secs())
 }
 
-pub async fn get_r_value(pool: Pool<MySql>, mail_time_stamp: u64) -> Result<u64> {
-    // Find the maximum R value for mails with similar timestamp prefix
-    let query = format!(
-        "SELECT MAX(CONVERT(SUBSTR(REGEXP_SUBSTR(`remoteId`,'R[0-9]+'),2),UNSIGNED)) AS `r_value` \
-             FROM `pimitemtable` \
-             WHERE `mimeTypeId` = 2 \
-             AND `remoteId` LIKE '{}%' \
-             AND `collectionId` IN (SELECT id FROM `collectiontable` WHERE `resourceId` = 3)",
-        mail_time_stamp
-    );
-
-    // Execute the query
-    let result: Option<(Option<u64>,)> = sqlx::query_as(&query).fetch_optional(&pool).await?;
-
-    if let Some((Some(r_value),)) = result {
-        // If an R value exists, increment it by a random number between 1 and 50
-        // to avoid collisions with existing mails
-        Ok(r_value + rand::rng().random_range(1..=50))
-    } else {
-        // If no R value exists, start with a random number between 20 and 950
-        Ok(rand::rng().random_range(20..=950))
+/// Default mapping of Akonadi flags to maildir flag letters.
+pub const DEFAULT_FLAG_MAP: [(&str, char); 7] = [
+    ("\\SEEN", 'S'),
+    ("\\ANSWERED", 'R'),
+    ("$REPLIED", 'R'),
+    ("$FORWARDED", 'P'),
+    ("\\FLAGGED", 'F'),
+    ("\\DELETED", 'T'),
+    ("\\DRAFT", 'D'),
+];
+
+/// Flags Akonadi derives from the mail content on import. Dropping them
+/// loses no information.
+const DERIVED_FLAGS: [&str; 7] = [
+    "$ATTACHMENT",
+    "$HASATTACHMENT",
+    "$HASNOATTACHMENT",
+    "$ENCRYPTED",
+    "$SIGNED",
+    "$INVITATION",
+    "$ERROR",
+];
+
+/// Maildir flag letters of a pim item.
+#[derive(Debug, Default, PartialEq)]
+pub struct MailFlags {
+    pub letters: Vec<char>,
+    /// Flags without a letter, lost when Akonadi re-imports the file
+    pub dropped: Vec<String>,
+}
+
+/// Build the mapping of Akonadi flag names to maildir flag letters from
+/// `DEFAULT_FLAG_MAP` and the `--flag-map` options. Flag names are matched
+/// in full and case-insensitively; an empty letter drops a default mapping.
+pub fn get_flag_map(args: &CliArgs) -> Result<HashMap<String, char>> {
+    let mut flag_map: HashMap<String, char> = DEFAULT_FLAG_MAP
+        .iter()
+        .map(|(name, letter)| (name.to_string(), *letter))
+        .collect();
+    for mapping in &args.flag_map {
+        let Some((name, letter)) = mapping.rsplit_once('=') else {
+            anyhow::bail!("Invalid flag mapping {}, expected FLAG=LETTER", mapping);
+        };
+        let name = name.trim().to_uppercase();
+        let mut letters = letter.trim().chars();
+        match (letters.next(), letters.next()) {
+            (None, _) => {
+                flag_map.remove(&name);
+            }
+            (Some(letter), None) if letter.is_ascii_uppercase() => {
+                flag_map.insert(name, letter);
+            }
+            _ => anyhow::bail!(
+                "Invalid flag mapping {}, the letter must be a single upper case ASCII letter",
+                mapping
+            ),
+        }
+    }
+    Ok(flag_map)
+}
+
+/// Get the maildir info suffix of a pim item. Flags without a maildir
+/// letter are reported in verbose and dry-run mode.
+///
+/// # Arguments
+/// - `file_id`: Pim item id
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `folder`: Maildir folder of the item
+///
+/// Returns `Result<String>`
+///
+pub async fn get_mail_info(
+    file_id: i64,
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    folder: &str,
+) -> Result<String> {
+    let mut flags = get_mail_flags(file_id, pool, &get_flag_map(args)?).await?;
+    assign_keyword_letters(&mut flags, folder, args)?;
+    if !flags.dropped.is_empty() && (args.verbose || args.dry_run) {
+        println!(
+            "Item ID {}: flags without maildir letter are lost on re-import: {}",
+            file_id,
+            flags.dropped.join(", ")
+        );
+    }
+    Ok(build_mail_info(flags.letters))
+}
+
+/// With `--dovecot-keywords`, turn the flags without a maildir letter into
+/// keyword letters `a`-`z` of the folder's `dovecot-keywords` file, which
+/// is updated unless in dry-run mode. Flags beyond the 26 letters stay dropped.
+pub fn assign_keyword_letters(flags: &mut MailFlags, folder: &str, args: &CliArgs) -> Result<()> {
+    if !args.dovecot_keywords || flags.dropped.is_empty() {
+        return Ok(());
+    }
+    let mut keywords = DovecotKeywords::load(folder)?;
+    apply_keyword_letters(flags, &mut keywords);
+    if !args.dry_run {
+        keywords.save()?;
     }
+    Ok(())
+}
+
+/// Turn the flags without a maildir letter into keyword letters of a loaded
+/// `dovecot-keywords` file. New keywords get free letters in memory only;
+/// writing them is left to the caller.
+pub fn apply_keyword_letters(flags: &mut MailFlags, keywords: &mut DovecotKeywords) {
+    flags
+        .dropped
+        .retain(|name| match keywords.letter_for(name) {
+            Some(letter) => {
+                flags.letters.push(letter);
+                false
+            }
+            None => true,
+        });
 }
 
-pub async fn get_mail_info(file_id: i64, pool: Pool<MySql>) -> Result<String> {
-    // Fetch mail flags from the database and construct the mail info string
-    // the `flagtable`.`id` entries, might be different for each user.
-    // Hence they should not be used in SQL queries. Instead a four letter
-    // acronym `flag` is used instead.
-    let query = format!(
-        "SELECT SUBSTR(CONVERT(`flagtable`.`name`, CHAR), 2,4) AS `flag`
-         FROM `pimitemflagrelation` RIGHT JOIN `flagtable`
+/// Fetch the flags of a pim item and map them to maildir flag letters.
+///
+/// # Arguments
+/// - `file_id`: Pim item id
+/// - `pool`: Database connection pool
+/// - `flag_map`: Mapping of upper case flag names to letters, see `get_flag_map`
+///
+/// Returns the letters and the names of the flags without a letter,
+/// except those derived from the mail content
+///
+pub async fn get_mail_flags(
+    file_id: i64,
+    pool: Pool<MySql>,
+    flag_map: &HashMap<String, char>,
+) -> Result<MailFlags> {
+    let names = fetch_flag_names(file_id, pool).await?;
+    Ok(map_flag_names(&names, flag_map))
+}
+
+/// Fetch the names of the flags of a pim item in alphabetical order.
+pub async fn fetch_flag_names(file_id: i64, pool: Pool<MySql>) -> Result<Vec<String>> {
+    // The `flagtable`.`id` entries might be different for each user.
+    // Hence they are not used in SQL queries, the flags are matched by name.
+    let rows: Vec<(String,)> = sqlx::query_as(
+        "SELECT CONVERT(`flagtable`.`name`, CHAR)
+         FROM `pimitemflagrelation` JOIN `flagtable`
            ON `pimitemflagrelation`.`Flag_id` = `flagtable`.`id`
-        WHERE `pimitemflagrelation`.`PimItem_id` = {}",
-        file_id
-    );
-    // Map of flags to their corresponding characters
-    let flag_map: HashMap<&str, &str> = HashMap::from([
-        ("SEEN", "S"),
-        ("FORW", "P"),
-        ("ANSW", "R"),
-        ("REPL", "R"),
-        ("FLAG", "F"),
-        ("DELE", "T"),
-    ]);
-
-    // Execute the query to get flags
-    let rows: Vec<(String,)> = sqlx::query_as(&query).fetch_all(&pool).await?;
-
-    // Construct the mail info string based on the fetched flags
-    if rows.is_empty() {
-        //
-        Ok(String::new())
-    } else {
-        //
-        let mut flags = rows
-            .into_iter()
-            .filter_map(|(flag,)| flag_map.get(flag.as_str()).copied())
-            .collect::<Vec<&str>>();
-        flags.sort();
-        flags.dedup();
-        let flag_string = flags.join("");
-        if !flag_string.is_empty() {
-            // Prepend :2, if there are flags
-            Ok(":2,".to_string() + &flag_string)
-        } else {
-            // No flags found - return empty string
-            Ok(String::new())
+        WHERE `pimitemflagrelation`.`PimItem_id` = ?
+        ORDER BY `flagtable`.`name`",
+    )
+    .bind(file_id)
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows.into_iter().map(|(name,)| name).collect())
+}
+
+/// Map flag names to maildir flag letters.
+/// Flags derived from the mail content are skipped, the names of all
+/// other flags without a letter are returned in `dropped`.
+pub fn map_flag_names(names: &[String], flag_map: &HashMap<String, char>) -> MailFlags {
+    let mut flags = MailFlags::default();
+    for name in names {
+        let upper = name.to_uppercase();
+        match flag_map.get(&upper) {
+            Some(letter) => flags.letters.push(*letter),
+            None if !DERIVED_FLAGS.contains(&upper.as_str()) => flags.dropped.push(name.clone()),
+            None => {}
         }
     }
+    flags
+}
+
+/// Build the maildir info suffix `:2,<flags>` from flag letters.
+/// Letters are sorted in ASCII order and duplicates are removed.
+/// Returns an empty string if there are no flags.
+pub fn build_mail_info(mut flags: Vec<char>) -> String {
+    flags.sort();
+    flags.dedup();
+    if flags.is_empty() {
+        // No flags found - return empty string
+        String::new()
+    } else {
+        // Prepend :2, if there are flags
+        format!(":2,{}", flags.into_iter().collect::<String>())
+    }
+}
+
+/// Split a maildir file name into its unique part and the flag letters
+/// of its info suffix `:2,<flags>`.
+pub fn split_mail_name(name: &str) -> (&str, Vec<char>) {
+    match name.split_once(':') {
+        Some((unique, info)) => (
+            unique,
+            info.strip_prefix("2,")
+                .map(|flags| flags.chars().collect())
+                .unwrap_or_default(),
+        ),
+        None => (name, Vec::new()),
+    }
 }
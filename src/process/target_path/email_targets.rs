@@ -33,6 +33,14 @@
 ///
 /// # Test Cases
 ///
+/// - `test_get_unique_mail_name`: Verifies that names of both naming schemes are kept.
+/// - `test_sanitize_hostname`: Verifies the escaping of `/` and `:` in host names.
+/// - `test_get_flag_map`: Verifies the default flag mapping including `\DRAFT` and its
+///   overrides by full flag name.
+/// - `test_get_mail_flags`: Verifies that unmapped flags are reported as dropped,
+///   except flags derived from the mail content.
+/// - `test_parse_received_date`: Verifies the fallback to the newest `Received:` header
+///   after removing the Date header of a mail.
 /// - `test_keep_name_for_email_in_maildir`: Verifies that existing remote_id
 ///   is preserved in the new file name for dry-run false.
 /// - `test_keep_name_for_email_in_maildir_dry_run`: Verifies that existing remote_id
@@ -58,10 +66,15 @@
 mod tests {
 
     use crate::cmdline::CliArgs;
+    use crate::context::RunContext;
     use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
     use crate::process::{
-        maildirs::fetch_full_paths, source_path::get_source_file_name,
-        target_path::get_target_file_name,
+        maildirs::fetch_full_paths,
+        source_path::get_source_file_name,
+        target_path::{
+            get_flag_map, get_mail_flags, get_target_file_name, get_unique_mail_name,
+            parse_mail_date, parse_received_date, sanitize_hostname,
+        },
     };
     use crate::todoitems::TodoPimItem;
     use anyhow::Result;
@@ -118,6 +131,8 @@ mod tests {
             item,
             full_paths,
             &source_file_name, // use the extracted time_stamp
+            args,
+            &RunContext::default(),
         )
         .await?;
         assert!(!target_file_name.is_empty());
@@ -127,14 +142,14 @@ mod tests {
         assert!(target_file_name.contains(&args.maildir_path));
         assert!(!target_file_name.contains("//"));
         // Verify that the remote_id part is preserved in the target file name
-        let re = Regex::new(r"(\d+\.R\d+\.\w+)").unwrap();
-        if let Some(caps) = re.captures(&source_file_name) {
-            assert!(target_file_name.contains(caps.get(1).unwrap().as_str()));
+        if let Some(mail_name) = get_unique_mail_name(&source_file_name) {
+            assert!(target_file_name.contains(mail_name));
         } else {
             // If the source file name does not match the email name pattern,
             // then the target file name should contain a new email name starting
             // with the expected timestamp.
-            assert!(target_file_name.contains(&format!("{}.R", expected_timestamp)));
+            let re = Regex::new(&format!(r"/{}\.M\d+P\d+R\d+\.", expected_timestamp))?;
+            assert!(re.is_match(&target_file_name));
         }
         // Verify that the "SEEN" flag is appended; this must be independent of hostname
         assert!(target_file_name.ends_with(":2,S"));
@@ -142,6 +157,88 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_unique_mail_name() {
+        assert_eq!(
+            get_unique_mail_name("1491255228.R505.helios:2,PS"),
+            Some("1491255228.R505.helios")
+        );
+        assert_eq!(
+            get_unique_mail_name("1767111571.M123456P4711R99.my-host"),
+            Some("1767111571.M123456P4711R99.my-host")
+        );
+        assert_eq!(get_unique_mail_name("1291727681.2020.4jNSG:2,S"), None);
+    }
+
+    #[test]
+    fn test_sanitize_hostname() {
+        assert_eq!(sanitize_hostname("helios"), "helios");
+        assert_eq!(sanitize_hostname("a/b:c"), "a\\057b\\072c");
+    }
+
+    #[test]
+    fn test_get_flag_map() -> Result<()> {
+        let mut args = CliArgs::default();
+        let flag_map = get_flag_map(&args)?;
+        assert_eq!(flag_map.get("\\DRAFT"), Some(&'D'));
+        assert_eq!(flag_map.get("\\SEEN"), Some(&'S'));
+        assert_eq!(flag_map.get("$SENT"), None);
+
+        args.flag_map = vec!["$replied=".to_string(), "$Junk = J".to_string()];
+        let flag_map = get_flag_map(&args)?;
+        assert_eq!(flag_map.get("$REPLIED"), None);
+        assert_eq!(flag_map.get("$JUNK"), Some(&'J'));
+
+        args.flag_map = vec!["$JUNK=j".to_string()];
+        assert!(get_flag_map(&args).is_err());
+        args.flag_map = vec!["$JUNK".to_string()];
+        assert!(get_flag_map(&args).is_err());
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../../tests/fixtures/akonadi.sql"))]
+    pub async fn test_get_mail_flags(pool: MySqlPool) -> Result<()> {
+        let flag_map = get_flag_map(&CliArgs::default())?;
+        // Item 50642 carries \SEEN and the derived $HasNoAttachment
+        let flags = get_mail_flags(50642, pool.clone(), &flag_map).await?;
+        assert_eq!(flags.letters, vec!['S']);
+        assert!(flags.dropped.is_empty());
+
+        // $QUEUED has no maildir letter
+        sqlx::query("INSERT INTO pimitemflagrelation (PimItem_id, Flag_id) VALUES (50642, 10)")
+            .execute(&pool)
+            .await?;
+        let flags = get_mail_flags(50642, pool.clone(), &flag_map).await?;
+        assert_eq!(flags.letters, vec!['S']);
+        assert_eq!(flags.dropped, vec!["$QUEUED".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_received_date() -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mail = format!(
+            "{}/local_mail/.inbox.directory/temporary/new/1291727681.2020.4jNSG:2,S",
+            temp_dir
+        );
+        assert_eq!(parse_mail_date(&mail)?, Some(1686315625));
+        // Drop the Date header
+        let content = std::fs::read(&mail)?;
+        let content: Vec<u8> = content
+            .split_inclusive(|b| *b == b'\n')
+            .filter(|line| !line.starts_with(b"Date:"))
+            .flatten()
+            .copied()
+            .collect();
+        std::fs::write(&mail, content)?;
+        assert_eq!(parse_mail_date(&mail)?, None);
+        // Fri, 9 Jun 2023 13:00:27 +0000, folded over two lines
+        assert_eq!(parse_received_date(&mail)?, Some(1686315627));
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
     #[sqlx::test(fixtures("../../../tests/fixtures/akonadi.sql"))]
     pub async fn test_keep_name_for_email_in_maildir(pool: MySqlPool) -> Result<()> {
         // Recursively copy src/todoitems/tests/data to a unique subdirectory in /tmp
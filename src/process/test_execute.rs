@@ -0,0 +1,157 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for moving files without overwriting existing targets.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location and move a copy of a mail file
+/// within the folder `to_be_filed`.
+///
+/// # Test Cases
+///
+/// - `test_move_file`: Verifies that a free target is taken over and the source removed.
+/// - `test_move_file_identical_target`: Verifies that an identical target is kept and
+///   the source dropped as a duplicate.
+/// - `test_move_file_collision`: Verifies that a different target is neither replaced
+///   nor the source removed.
+/// - `test_copy_file`: Verifies the cross-filesystem fallback: the copy equals the
+///   source, the source is removed, no staging file is left in `tmp`, and an
+///   existing target is never replaced.
+/// - `test_set_mail_times`: Verifies that the file times are set from the Date header
+///   and left alone if already correct.
+/// - `test_write_file_durably`: Verifies that new files are staged in the folder's
+///   `tmp` directory and never replace an existing file.
+///
+mod tests {
+    use crate::mockup::{setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use crate::process::execute::{
+        copy_file, file_digest, get_staging_path, move_file, set_mail_times, write_file_durably,
+        MoveOutcome,
+    };
+    use anyhow::Result;
+    use std::path::Path;
+
+    const FOLDER: &str = "local_mail/.inbox.directory/to_be_filed/";
+    const MAIL: &str = "new/1491255228.R505.helios:2,PS";
+
+    /// Copy the test mail to `tmp` and return source and mail path.
+    fn setup_source(temp_dir: &str) -> Result<(String, String)> {
+        let mail = format!("{}/{}{}", temp_dir, FOLDER, MAIL);
+        std::fs::create_dir_all(format!("{}/{}tmp", temp_dir, FOLDER))?;
+        let source = format!("{}/{}tmp/copy", temp_dir, FOLDER);
+        std::fs::copy(&mail, &source)?;
+        Ok((source, mail))
+    }
+
+    #[test]
+    fn test_move_file() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let (source, _) = setup_source(&temp_dir)?;
+        let target = format!("{}/{}cur/1.M1P1R1.host:2,S", temp_dir, FOLDER);
+
+        assert_eq!(move_file(&source, &target)?, MoveOutcome::Moved);
+        assert!(!Path::new(&source).exists());
+        assert!(Path::new(&target).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_file_identical_target() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let (source, mail) = setup_source(&temp_dir)?;
+
+        assert_eq!(move_file(&source, &mail)?, MoveOutcome::Duplicate);
+        assert!(!Path::new(&source).exists());
+        assert!(Path::new(&mail).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_file_collision() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let (source, mail) = setup_source(&temp_dir)?;
+        let content = std::fs::read(&mail)?;
+        std::fs::write(&source, b"Subject: different\n\nbody\n")?;
+
+        assert_eq!(move_file(&source, &mail)?, MoveOutcome::Collision);
+        assert!(Path::new(&source).exists());
+        assert_eq!(std::fs::read(&mail)?, content);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_file() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let (source, mail) = setup_source(&temp_dir)?;
+        let digest = file_digest(&source)?;
+        let target = format!("{}/{}cur/1.M1P1R1.host:2,S", temp_dir, FOLDER);
+
+        assert_eq!(copy_file(&source, &target)?, MoveOutcome::Moved);
+        assert!(!Path::new(&source).exists());
+        assert_eq!(file_digest(&target)?, digest);
+        let tmp_dir = format!("{}/{}tmp", temp_dir, FOLDER);
+        assert_eq!(std::fs::read_dir(&tmp_dir)?.count(), 0);
+
+        std::fs::write(&source, b"Subject: different\n\nbody\n")?;
+        assert_eq!(copy_file(&source, &mail)?, MoveOutcome::Collision);
+        assert!(Path::new(&source).exists());
+        assert_eq!(file_digest(&mail)?, digest);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_file_durably() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let target = format!("{}/{}new/1.M1P1R1.host", temp_dir, FOLDER);
+        let staging = get_staging_path(&target)?;
+        assert!(staging.starts_with(&format!("{}/{}tmp/", temp_dir, FOLDER)));
+        let outside = format!("{}/file_db_data/payload", temp_dir);
+        assert_eq!(get_staging_path(&outside)?, outside);
+
+        write_file_durably(&staging, b"Subject: test\n\nbody\n")?;
+        assert_eq!(std::fs::read(&staging)?, b"Subject: test\n\nbody\n");
+        assert!(write_file_durably(&staging, b"other").is_err());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_mail_times() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let mail = format!("{}/{}{}", temp_dir, FOLDER, MAIL);
+        // Date: Mon, 03 Apr 2017 23:33:48 +0200
+        let date = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1491255228);
+
+        assert!(set_mail_times(&mail)?);
+        let metadata = std::fs::metadata(&mail)?;
+        assert_eq!(metadata.modified()?, date);
+        assert_eq!(metadata.accessed()?, date);
+        assert!(!set_mail_times(&mail)?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for Dovecot keyword letters.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location and use the folder `to_be_filed`.
+///
+/// # Test Cases
+///
+/// - `test_keywords_round_trip`: Verifies that letters assigned to keywords are saved
+///   and found again after loading the `dovecot-keywords` file.
+/// - `test_keywords_existing_file`: Verifies that letters of an existing file are kept
+///   and new keywords fill the gaps.
+/// - `test_keyword_for_letter`: Verifies that letters are mapped back to their keywords.
+/// - `test_keyword_letters_in_file_name`: Verifies that flags without maildir letter end
+///   up as keyword letters in the info suffix and in the keywords file.
+///
+mod tests {
+    use crate::cmdline::CliArgs;
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use crate::process::keywords::DovecotKeywords;
+    use crate::process::target_path::get_mail_info;
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+
+    const FOLDER: &str = "local_mail/.inbox.directory/to_be_filed/";
+
+    #[test]
+    fn test_keywords_round_trip() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+
+        let mut keywords = DovecotKeywords::load(&folder)?;
+        assert_eq!(keywords.letter_for("$TODO"), Some('a'));
+        assert_eq!(keywords.letter_for("$Junk"), Some('b'));
+        assert_eq!(keywords.letter_for("$todo"), Some('a'));
+        keywords.save()?;
+        assert_eq!(
+            std::fs::read_to_string(format!("{}dovecot-keywords", folder))?,
+            "0 $TODO\n1 $Junk\n"
+        );
+
+        let mut keywords = DovecotKeywords::load(&folder)?;
+        assert_eq!(keywords.letter_for("$Junk"), Some('b'));
+        assert_eq!(keywords.letter_for("$TODO"), Some('a'));
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_keywords_existing_file() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        std::fs::write(
+            format!("{}dovecot-keywords", folder),
+            "1 $NotJunk\n3 $Work\n",
+        )?;
+
+        let mut keywords = DovecotKeywords::load(&folder)?;
+        assert_eq!(keywords.letter_for("$Work"), Some('d'));
+        assert_eq!(keywords.letter_for("$IGNORED"), Some('a'));
+        for i in 0..23 {
+            assert!(keywords.letter_for(&format!("$K{}", i)).is_some());
+        }
+        assert_eq!(keywords.letter_for("$Overflow"), None);
+        keywords.save()?;
+        let content = std::fs::read_to_string(format!("{}dovecot-keywords", folder))?;
+        assert!(content.starts_with("0 $IGNORED\n1 $NotJunk\n2 $K0\n3 $Work\n"));
+        assert_eq!(content.lines().count(), 26);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyword_for_letter() -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        std::fs::write(
+            format!("{}dovecot-keywords", folder),
+            "1 $NotJunk
+",
+        )?;
+
+        let keywords = DovecotKeywords::load(&folder)?;
+        assert_eq!(keywords.keyword_for('b'), Some("$NotJunk"));
+        assert_eq!(keywords.keyword_for('a'), None);
+        assert_eq!(keywords.keyword_for('S'), None);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_keyword_letters_in_file_name(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir = setup_tmp_mail_dir()?;
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        // $IGNORED and $QUEUED have no maildir letter
+        sqlx::query(
+            "INSERT INTO pimitemflagrelation (PimItem_id, Flag_id) VALUES (50642, 2), (50642, 10)",
+        )
+        .execute(&pool)
+        .await?;
+
+        let args = CliArgs {
+            dovecot_keywords: true,
+            ..create_test_cli_args(&temp_dir, false)
+        };
+        assert_eq!(
+            get_mail_info(50642, pool.clone(), &args, &folder).await?,
+            ":2,Sab"
+        );
+        assert_eq!(
+            std::fs::read_to_string(format!("{}dovecot-keywords", folder))?,
+            "0 $IGNORED\n1 $QUEUED\n"
+        );
+
+        let args = create_test_cli_args(&temp_dir, false);
+        assert_eq!(
+            get_mail_info(50642, pool.clone(), &args, &folder).await?,
+            ":2,S"
+        );
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
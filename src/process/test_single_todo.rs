@@ -54,8 +54,12 @@
 /// - `test_email_in_database()`: Tests that an email stored in the database is moved to the target
 ///   maildir and the item is removed from the database. It verifies that the email file exists
 ///   in the target maildir and that the item ID is cleared from the database.
+/// - `test_email_updated_in_place()`: Tests that with `--in-place` an email in the "new" directory
+///   is moved to "cur" and its item keeps its row with the new remote ID, a cleared dirty flag and
+///   an increased revision.
 ///
 mod tests {
+    use crate::context::RunContext;
     use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
     use crate::process::{
         maildirs::fetch_full_paths, process_single_todo_item, source_path::get_single_matching_file,
@@ -150,7 +154,14 @@ mod tests {
         };
         let expected_timestamp = 1686315625; // Extracted from email content
 
-        let result = process_single_todo_item(pool.clone(), &item, &full_paths, &args).await;
+        let result = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -190,7 +201,14 @@ mod tests {
         };
         let expected_timestamp = 1330783242; // Extracted from email name
 
-        let result = process_single_todo_item(pool.clone(), &item, &full_paths, &args).await;
+        let result = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -241,7 +259,14 @@ mod tests {
             collection_id: 42,
         };
 
-        let result = process_single_todo_item(pool.clone(), &item, &full_paths, &args).await;
+        let result = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -284,7 +309,14 @@ mod tests {
             collection_id: 66,
         };
 
-        let result = process_single_todo_item(pool.clone(), &item, &full_paths, &args).await;
+        let result = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -337,7 +369,14 @@ mod tests {
         };
         let expected_timestamp = 1491255228; // Extracted from email name
 
-        let result = process_single_todo_item(pool.clone(), &item, &full_paths, &args).await;
+        let result = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await;
         assert!(
             result.is_ok(),
             "Processing single todo item failed: {:?}",
@@ -377,7 +416,14 @@ mod tests {
         };
         let expected_timestamp = 1767092602; // Extracted from email content
 
-        let result = process_single_todo_item(pool.clone(), &item, &full_paths, &args).await;
+        let result = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await;
         assert!(
             result.is_ok(),
             "Processing single todo item failed: {:?}",
@@ -416,7 +462,14 @@ mod tests {
         };
         let expected_timestamp = 1767111447; // Extracted from email content
 
-        let result = process_single_todo_item(pool.clone(), &item, &full_paths, &args).await;
+        let result = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await;
         assert!(
             result.is_ok(),
             "Processing single todo item failed: {:?}",
@@ -431,4 +484,45 @@ mod tests {
 
         Ok(())
     }
+
+    /// Test case to verify that with `--in-place` the item of a renamed email keeps its row.
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    pub async fn test_email_updated_in_place(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+        args.in_place = true;
+        let full_paths: std::collections::HashMap<i64, String> =
+            fetch_full_paths(pool.clone(), &args).await?;
+
+        // Email in "new" whose item carries \SEEN
+        let item = TodoPimItem {
+            id: 50628,
+            remote_id: Some("1767111571664.R424.helios".to_string()),
+            collection_id: 394,
+        };
+        process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await?;
+
+        let target = format!("{}cur/1767111571664.R424.helios:2,S", full_paths[&394]);
+        assert!(std::path::Path::new(&target).exists());
+        let (remote_id, dirty, rev): (String, i8, i32) = sqlx::query_as(
+            "SELECT CONVERT(remoteId, CHAR), dirty, rev FROM pimitemtable WHERE id = ?",
+        )
+        .bind(item.id)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(remote_id, "1767111571664.R424.helios:2,S");
+        assert_eq!(dirty, 0);
+        assert_eq!(rev, 2);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+
+        Ok(())
+    }
 }
@@ -14,6 +14,7 @@
 
 #[cfg(test)]
 mod test {
+    use crate::context::RunContext;
     use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
     use crate::process::process_todo_items;
     use crate::todoitems::{fetch_todo_pim_items, TodoPimItem};
@@ -47,7 +48,8 @@ mod test {
         assert!(result.is_ok());
 
         // Assert that a second call to fetch_todo_pim_items returns an empty vector
-        let remaining_items: Vec<TodoPimItem> = fetch_todo_pim_items(pool.clone(), &args).await?;
+        let remaining_items: Vec<TodoPimItem> =
+            fetch_todo_pim_items(pool.clone(), &args, &RunContext::default()).await?;
         assert!(remaining_items.is_empty());
 
         // Assert that the temporary mail directory contains no files after processing
@@ -0,0 +1,281 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::{CliArgs, ConflictPolicy};
+use crate::impact::{report_delete_impact, report_delete_totals, DeleteImpact};
+use crate::process::execute::{move_mail, update_akonadi_db};
+use crate::process::keywords::DovecotKeywords;
+use crate::process::maildirs::fetch_full_paths;
+use crate::process::target_path::{
+    apply_keyword_letters, build_mail_info, get_flag_map, get_mail_flags, split_mail_name,
+};
+use crate::special::{fetch_special_collections, is_queued_item};
+use anyhow::Result;
+use sqlx::{FromRow, MySql, Pool};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+
+#[cfg(test)]
+pub(crate) mod test_reconcile;
+
+/// Item whose flags differ between its file name and Akonadi.
+#[derive(Debug)]
+pub struct FlagConflict {
+    pub item_id: i64,
+    pub remote_id: String,
+    /// Mail file found by the unique part of the remote ID
+    pub path: String,
+    pub disk: Vec<char>,
+    pub db: Vec<char>,
+    pub resolved: Vec<char>,
+    /// Path of the mail file carrying the resolved flags
+    pub target: String,
+}
+
+#[derive(Debug, FromRow)]
+struct FlaggedItem {
+    id: i64,
+    remote_id: String,
+    collection_id: i64,
+    /// Last modification of the item in seconds since UNIX_EPOCH
+    modified: Option<i64>,
+}
+
+/// Sort and deduplicate flag letters.
+fn normalize_letters(mut letters: Vec<char>) -> Vec<char> {
+    letters.sort();
+    letters.dedup();
+    letters
+}
+
+/// Resolve the flag letters of a file name and of an Akonadi item.
+///
+/// # Arguments
+/// - `policy`: Conflict policy
+/// - `disk`: Letters of the file name
+/// - `db`: Letters of the Akonadi flags
+/// - `disk_is_newer`: Whether the file was changed after the item was
+///   modified, or the latter is unknown
+///
+/// Returns the sorted letters
+///
+pub fn resolve_flags(
+    policy: ConflictPolicy,
+    disk: &[char],
+    db: &[char],
+    disk_is_newer: bool,
+) -> Vec<char> {
+    let letters = match policy {
+        ConflictPolicy::Union => [disk, db].concat(),
+        ConflictPolicy::DiskWins => disk.to_vec(),
+        ConflictPolicy::DbWins => db.to_vec(),
+        ConflictPolicy::Newest if disk_is_newer => disk.to_vec(),
+        ConflictPolicy::Newest => db.to_vec(),
+    };
+    normalize_letters(letters)
+}
+
+/// Find all items of the local folders whose flags differ between the file
+/// name on disk and `pimitemflagrelation`. The file is found by the unique
+/// part of the remote ID, such that files renamed by another client are
+/// found, too. Dirty items are left to the todo processing. Letters the
+/// flag mapping does not cover are kept as they are.
+///
+/// The `newest` policy compares the status change time of the file with
+/// `pimitemtable.datetime`, the modification time of the item; `atime` is
+/// bumped by every fetch. Without `datetime` the file wins.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `policy`: Conflict policy
+/// - `keywords`: `dovecot-keywords` of each collection, loaded on demand;
+///   new keyword letters are assigned in memory only
+///
+/// Returns `Result<Vec<FlagConflict>>`
+///
+pub async fn find_flag_conflicts(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    policy: ConflictPolicy,
+    keywords: &mut HashMap<i64, DovecotKeywords>,
+) -> Result<Vec<FlagConflict>> {
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let flag_map = get_flag_map(args)?;
+    let special = fetch_special_collections(pool.clone()).await?;
+    let items = sqlx::query_as::<_, FlaggedItem>(
+        "SELECT `id`,
+            CONVERT(`remoteId`, CHAR) AS `remote_id`,
+            `collectionId` AS `collection_id`,
+            CAST(UNIX_TIMESTAMP(`datetime`) AS SIGNED) AS `modified`
+        FROM `pimitemtable`
+        WHERE `mimeTypeId` = 2 AND `dirty` = 0 AND `remoteId` IS NOT NULL
+        ORDER BY `id`",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    // Files of each folder by the unique part of their name
+    let mut files: HashMap<i64, HashMap<String, Vec<String>>> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for item in items {
+        let Some(folder) = full_paths.get(&item.collection_id) else {
+            continue;
+        };
+        if is_queued_item(pool.clone(), &special, item.id, item.collection_id).await? {
+            // KMail has yet to send it
+            continue;
+        }
+        let folder_files = files
+            .entry(item.collection_id)
+            .or_insert_with(|| list_mail_files(folder));
+        let (unique, _) = split_mail_name(&item.remote_id);
+        let Some([path]) = folder_files.get(unique).map(|paths| paths.as_slice()) else {
+            // Missing files are handled by the todo processing, several
+            // files with the same name by `dedupe`
+            continue;
+        };
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let letters = split_mail_name(name).1;
+        // Letters the mapping cannot produce take no part in the comparison
+        let mut flags = get_mail_flags(item.id, pool.clone(), &flag_map).await?;
+        if args.dovecot_keywords && !flags.dropped.is_empty() {
+            let keywords = match keywords.entry(item.collection_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(DovecotKeywords::load(folder)?),
+            };
+            apply_keyword_letters(&mut flags, keywords);
+        }
+        let known: Vec<char> = flag_map
+            .values()
+            .copied()
+            .chain(flags.letters.iter().copied())
+            .collect();
+        let (disk, kept): (Vec<char>, Vec<char>) = letters
+            .iter()
+            .partition(|l| known.contains(l) || (args.dovecot_keywords && l.is_ascii_lowercase()));
+        let disk = normalize_letters(disk);
+        let db = normalize_letters(flags.letters);
+        if disk == db {
+            continue;
+        }
+        let disk_is_newer = std::fs::metadata(path).is_ok_and(|m| Some(m.ctime()) > item.modified);
+        let resolved = resolve_flags(policy, &disk, &db, disk_is_newer);
+        let mail_info = build_mail_info([resolved.clone(), kept].concat());
+        let cur_new_name = if mail_info.is_empty() { "new" } else { "cur" };
+        let target = format!("{}{}/{}{}", folder, cur_new_name, unique, mail_info);
+        conflicts.push(FlagConflict {
+            item_id: item.id,
+            remote_id: item.remote_id,
+            path: path.clone(),
+            disk,
+            db,
+            resolved,
+            target,
+        });
+    }
+    Ok(conflicts)
+}
+
+/// Files in `new` and `cur` of a folder by the unique part of their name.
+fn list_mail_files(folder: &str) -> HashMap<String, Vec<String>> {
+    let mut files: HashMap<String, Vec<String>> = HashMap::new();
+    for sub_dir in ["new", "cur"] {
+        let Ok(entries) = std::fs::read_dir(format!("{}{}", folder, sub_dir)) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            files
+                .entry(split_mail_name(&name).0.to_string())
+                .or_default()
+                .push(entry.path().to_string_lossy().to_string());
+        }
+    }
+    files
+}
+
+/// Reconcile the flags in the file names with the flags of the Akonadi
+/// items. The resolved flags are written into the file name, and the item
+/// is removed from the database, such that Akonadi re-imports the file
+/// with the resolved flags at the next sync.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `policy`: Conflict policy
+///
+/// Returns `Result<()>`
+///
+pub async fn reconcile_flags(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    policy: ConflictPolicy,
+) -> Result<()> {
+    let mut deleted = DeleteImpact::default();
+    let dry_run_msg_would = if args.dry_run { "Would move" } else { "Moving" };
+    let mut keywords = HashMap::new();
+    let conflicts = find_flag_conflicts(pool.clone(), args, policy, &mut keywords).await?;
+    let mut reconciled = 0;
+    // Keyword letters used in the new file names must be known to Dovecot
+    if !args.dry_run {
+        for keywords in keywords.values_mut() {
+            keywords.save()?;
+        }
+    }
+
+    for conflict in &conflicts {
+        println!(
+            "Item ID {}: flags \"{}\" on disk, \"{}\" in Akonadi, resolved to \"{}\"",
+            conflict.item_id,
+            conflict.disk.iter().collect::<String>(),
+            conflict.db.iter().collect::<String>(),
+            conflict.resolved.iter().collect::<String>()
+        );
+        if args.verbose || args.dry_run {
+            println!("  remote ID {}", conflict.remote_id);
+            if conflict.path != conflict.target {
+                println!(
+                    "  {} {} to {}",
+                    dry_run_msg_would, conflict.path, conflict.target
+                );
+            }
+        }
+        if args.dry_run {
+            deleted.add(&report_delete_impact(pool.clone(), args, conflict.item_id).await?);
+            reconciled += 1;
+            continue;
+        }
+        let Some(moved) = move_mail(pool.clone(), args, &conflict.path, &conflict.target).await?
+        else {
+            continue;
+        };
+        deleted.add(&update_akonadi_db(pool.clone(), args, conflict.item_id, Some(&moved)).await?);
+        reconciled += 1;
+    }
+
+    println!(
+        "{} items with differing flags, {} {}.",
+        conflicts.len(),
+        reconciled,
+        if args.dry_run {
+            "would be reconciled"
+        } else {
+            "reconciled"
+        }
+    );
+    report_delete_totals(args, &deleted);
+    Ok(())
+}
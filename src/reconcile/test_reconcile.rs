@@ -0,0 +1,169 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the reconciliation of flags in file names and in Akonadi.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. Item 50628 carries `\SEEN` in
+/// Akonadi, while its file `nirwana/new/1767111571664.R424.helios` has no flags.
+/// The flags of all other items agree with their file names.
+///
+/// # Test Cases
+///
+/// - `test_resolve_flags`: Verifies the conflict policies.
+/// - `test_find_flag_conflicts`: Verifies that only item 50628 is reported and
+///   the target for the union and disk-wins policies.
+/// - `test_reconcile_flags`: Verifies that the file is renamed and the item is
+///   removed from the database.
+/// - `test_reconcile_flags_keywords`: Verifies that a keyword letter is assigned
+///   without writing `dovecot-keywords` while looking for conflicts and in
+///   dry-run mode, and written by a real run.
+///
+mod tests {
+    use crate::cmdline::ConflictPolicy;
+    use crate::mockup::{
+        create_test_cli_args, item_present, setup_tmp_mail_dir, teardown_tmp_mail_dir,
+    };
+    use crate::reconcile::{find_flag_conflicts, reconcile_flags, resolve_flags};
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    const FOLDER: &str = "local_mail/.inbox.directory/nirwana/";
+    const NAME: &str = "1767111571664.R424.helios";
+
+    #[test]
+    fn test_resolve_flags() {
+        let disk = ['P', 'S'];
+        let db = ['R', 'S'];
+        assert_eq!(
+            resolve_flags(ConflictPolicy::Union, &disk, &db, false),
+            vec!['P', 'R', 'S']
+        );
+        assert_eq!(
+            resolve_flags(ConflictPolicy::DiskWins, &disk, &db, false),
+            vec!['P', 'S']
+        );
+        assert_eq!(
+            resolve_flags(ConflictPolicy::DbWins, &disk, &db, true),
+            vec!['R', 'S']
+        );
+        assert_eq!(
+            resolve_flags(ConflictPolicy::Newest, &disk, &db, true),
+            vec!['P', 'S']
+        );
+        assert_eq!(
+            resolve_flags(ConflictPolicy::Newest, &disk, &db, false),
+            vec!['R', 'S']
+        );
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_find_flag_conflicts(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let conflicts = find_flag_conflicts(
+            pool.clone(),
+            &args,
+            ConflictPolicy::Union,
+            &mut HashMap::new(),
+        )
+        .await?;
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.item_id, 50628);
+        assert!(conflict.disk.is_empty());
+        assert_eq!(conflict.db, vec!['S']);
+        assert_eq!(conflict.resolved, vec!['S']);
+        assert!(conflict
+            .target
+            .ends_with(&format!("{}cur/{}:2,S", FOLDER, NAME)));
+
+        let conflicts = find_flag_conflicts(
+            pool.clone(),
+            &args,
+            ConflictPolicy::DiskWins,
+            &mut HashMap::new(),
+        )
+        .await?;
+        assert!(conflicts[0].resolved.is_empty());
+        assert_eq!(conflicts[0].target, conflicts[0].path);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_reconcile_flags(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        reconcile_flags(pool.clone(), &args, ConflictPolicy::Union).await?;
+        assert!(!Path::new(&format!("{}/{}new/{}", temp_dir, FOLDER, NAME)).exists());
+        assert!(Path::new(&format!("{}/{}cur/{}:2,S", temp_dir, FOLDER, NAME)).exists());
+        assert!(!item_present(pool.clone(), 50628).await?);
+        assert!(find_flag_conflicts(
+            pool.clone(),
+            &args,
+            ConflictPolicy::Union,
+            &mut HashMap::new()
+        )
+        .await?
+        .is_empty());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_reconcile_flags_keywords(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+        args.dovecot_keywords = true;
+        sqlx::query("INSERT INTO flagtable (id, name) VALUES (100, '$TODO')")
+            .execute(&pool)
+            .await?;
+        sqlx::query("INSERT INTO pimitemflagrelation VALUES (50628, 100)")
+            .execute(&pool)
+            .await?;
+        let keywords_file = format!("{}/{}dovecot-keywords", temp_dir, FOLDER);
+
+        let conflicts = find_flag_conflicts(
+            pool.clone(),
+            &args,
+            ConflictPolicy::Union,
+            &mut HashMap::new(),
+        )
+        .await?;
+        assert_eq!(conflicts[0].db, vec!['S', 'a']);
+        assert!(!Path::new(&keywords_file).exists());
+
+        args.dry_run = true;
+        reconcile_flags(pool.clone(), &args, ConflictPolicy::Union).await?;
+        assert!(!Path::new(&keywords_file).exists());
+
+        args.dry_run = false;
+        reconcile_flags(pool.clone(), &args, ConflictPolicy::Union).await?;
+        assert_eq!(std::fs::read_to_string(&keywords_file)?, "0 $TODO\n");
+        assert!(Path::new(&format!("{}/{}cur/{}:2,Sa", temp_dir, FOLDER, NAME)).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
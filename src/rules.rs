@@ -0,0 +1,255 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use sqlx::{MySql, Pool};
+use std::collections::HashMap;
+
+#[cfg(test)]
+pub(crate) mod test_rules;
+
+/// Reason for selecting an item of a folder for processing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Criterion {
+    /// Item kept in the Akonadi cache
+    Dirty,
+    /// File name without the seen flag `S`
+    Unseen,
+    /// Item flagged `\ANSWERED` whose file name lacks `RS`
+    Answered,
+    /// File with info suffix in a `new` directory
+    New,
+}
+
+impl Criterion {
+    pub const ALL: [Criterion; 4] = [
+        Criterion::Dirty,
+        Criterion::Unseen,
+        Criterion::Answered,
+        Criterion::New,
+    ];
+
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "dirty" => Ok(Criterion::Dirty),
+            "unseen" => Ok(Criterion::Unseen),
+            "answered" => Ok(Criterion::Answered),
+            "new" => Ok(Criterion::New),
+            _ => anyhow::bail!("unknown selection criterion {}", name),
+        }
+    }
+}
+
+/// Policy for the folders matching a pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderRule {
+    /// Glob matched against the collection path, e.g. `inbox/lists/*`,
+    /// or against the collection name if it holds no `/`
+    pub pattern: String,
+    /// Leave the folder alone
+    pub skip: bool,
+    pub select: Vec<Criterion>,
+    /// Letters always set in the target name
+    pub force: Vec<char>,
+    /// Letters never set in the target name
+    pub forbid: Vec<char>,
+}
+
+impl Default for FolderRule {
+    /// Every mail in a local folder is expected to be seen.
+    fn default() -> Self {
+        FolderRule {
+            pattern: "*".to_string(),
+            skip: false,
+            select: Criterion::ALL.to_vec(),
+            force: Vec::new(),
+            forbid: Vec::new(),
+        }
+    }
+}
+
+impl FolderRule {
+    /// Check whether the rule applies to a collection.
+    ///
+    /// # Arguments
+    /// - `path`: Collection names below the root joined by `/`
+    /// - `name`: Collection name
+    ///
+    pub fn matches(&self, path: &str, name: &str) -> bool {
+        let Ok(pattern) = Pattern::new(&self.pattern) else {
+            return false;
+        };
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        if self.pattern.contains('/') {
+            pattern.matches_with(path, options)
+        } else {
+            pattern.matches_with(name, options)
+        }
+    }
+
+    pub fn selects(&self, criterion: Criterion) -> bool {
+        !self.skip && self.select.contains(&criterion)
+    }
+
+    /// Add the forced and remove the forbidden letters.
+    pub fn apply_flags(&self, letters: &mut Vec<char>) {
+        letters.extend(&self.force);
+        letters.retain(|letter| !self.forbid.contains(letter));
+    }
+}
+
+/// Parse a rules file. Each line holds a pattern followed by options:
+/// `skip`, `select=<criterion>,...` with the criteria `dirty`, `unseen`,
+/// `answered` and `new`, `force=<letters>` and `forbid=<letters>`.
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// # Arguments
+/// - `text`: Content of the rules file
+///
+/// Returns the rules in the order of the file
+///
+pub fn parse_rules(text: &str) -> Result<Vec<FolderRule>> {
+    let mut rules = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let mut rule = FolderRule {
+            pattern: words.next().unwrap_or_default().to_string(),
+            ..FolderRule::default()
+        };
+        Pattern::new(&rule.pattern)
+            .map_err(|e| anyhow::anyhow!("Rule line {}: {}", number + 1, e))?;
+        for word in words {
+            let parsed = match word.split_once('=') {
+                None if word == "skip" => {
+                    rule.skip = true;
+                    Ok(())
+                }
+                Some(("select", criteria)) => criteria
+                    .split(',')
+                    .filter(|c| !c.is_empty())
+                    .map(Criterion::parse)
+                    .collect::<Result<Vec<_>>>()
+                    .map(|select| rule.select = select),
+                Some(("force", letters)) => parse_letters(letters).map(|l| rule.force = l),
+                Some(("forbid", letters)) => parse_letters(letters).map(|l| rule.forbid = l),
+                _ => Err(anyhow::anyhow!("unknown option {}", word)),
+            };
+            parsed.map_err(|e| anyhow::anyhow!("Rule line {}: {}", number + 1, e))?;
+        }
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+fn parse_letters(letters: &str) -> Result<Vec<char>> {
+    if let Some(letter) = letters.chars().find(|c| !c.is_ascii_alphabetic()) {
+        anyhow::bail!("invalid flag letter {}", letter);
+    }
+    Ok(letters.chars().collect())
+}
+
+/// Rules file below `$XDG_CONFIG_HOME/fix_local_mail/` (or `~/.config/...`)
+/// unless given explicitly.
+pub fn get_rules_path(args: &CliArgs) -> Result<String> {
+    if args.rules_path != "auto" {
+        return Ok(args.rules_path.clone());
+    }
+    let config_home = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => dir,
+        _ => format!("{}/.config", std::env::var("HOME")?),
+    };
+    Ok(format!("{}/fix_local_mail/rules.conf", config_home))
+}
+
+/// Fetch the path and the name of all local collections. The path joins
+/// the names below the root collection with `/`, e.g. `inbox/to_be_filed`.
+pub async fn fetch_collection_paths(pool: Pool<MySql>) -> Result<HashMap<i64, (String, String)>> {
+    let rows: Vec<(i64, String, Option<i64>)> = sqlx::query_as(
+        "SELECT `id`, CONVERT(`name`, CHAR), `parentId`
+        FROM `collectiontable`
+        WHERE `resourceId` = 3",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let parents: HashMap<i64, (String, Option<i64>)> = rows
+        .into_iter()
+        .map(|(id, name, parent_id)| (id, (name, parent_id)))
+        .collect();
+    let mut paths = HashMap::new();
+    for (id, (name, _)) in &parents {
+        let mut names = Vec::new();
+        let mut current = Some(*id);
+        while let Some((name, parent_id)) = current.and_then(|id| parents.get(&id)) {
+            // The root collection is not part of the path
+            if parent_id.is_none() || names.len() > parents.len() {
+                break;
+            }
+            names.push(name.as_str());
+            current = *parent_id;
+        }
+        names.reverse();
+        paths.insert(*id, (names.join("/"), name.clone()));
+    }
+    Ok(paths)
+}
+
+/// Resolve the rule of each local collection: the first rule of the rules
+/// file matching it, or the default rule. Without a rules file, the map is
+/// empty and the default rule applies everywhere.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<HashMap<i64, FolderRule>>` by collection id
+///
+pub async fn load_folder_rules(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+) -> Result<HashMap<i64, FolderRule>> {
+    let rules_path = get_rules_path(args)?;
+    let text = match std::fs::read_to_string(&rules_path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && args.rules_path == "auto" => {
+            return Ok(HashMap::new());
+        }
+        Err(e) => anyhow::bail!("Cannot read rules file {}: {}", rules_path, e),
+    };
+    let rules = parse_rules(&text)?;
+    let mut collections: Vec<(i64, (String, String))> =
+        fetch_collection_paths(pool).await?.into_iter().collect();
+    collections.sort_by(|a, b| a.1 .0.cmp(&b.1 .0));
+    let mut folder_rules = HashMap::new();
+    for (id, (path, name)) in collections {
+        let rule = rules
+            .iter()
+            .find(|rule| rule.matches(&path, &name))
+            .cloned()
+            .unwrap_or_default();
+        if args.verbose || args.dry_run {
+            println!("Folder {}: rule {}", path, rule.pattern);
+        }
+        folder_rules.insert(id, rule);
+    }
+    Ok(folder_rules)
+}
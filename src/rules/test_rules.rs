@@ -0,0 +1,141 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the per-folder rules.
+///
+/// # Test Setup
+///
+/// The database tests create a temporary mail directory structure by copying test
+/// data from `tests/data` to a unique temporary location and write the rules file
+/// there. Without rules, the fixture yields 9 todo items: items 50628, 50638,
+/// 50642, 50643 and 50645 in `nirwana`, item 132632 in the root collection and
+/// the files with info suffix in `new` of items 206 and 50377 in `temporary` and
+/// 1207 in `to_be_filed`.
+///
+/// # Test Cases
+///
+/// - `test_parse_rules`: Verifies parsing options, comments and errors.
+/// - `test_rule_matches`: Verifies matching by collection path and by name.
+/// - `test_apply_flags`: Verifies forced and forbidden letters.
+/// - `test_fetch_collection_paths`: Verifies the collection paths below the root.
+/// - `test_fetch_todo_items_with_rules`: Verifies that skipped folders and
+///   deselected criteria reduce the todo items.
+///
+mod tests {
+    use crate::context::RunContext;
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use crate::rules::{
+        fetch_collection_paths, load_folder_rules, parse_rules, Criterion, FolderRule,
+    };
+    use crate::todoitems::fetch_todo_pim_items;
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+
+    #[test]
+    fn test_parse_rules() -> Result<()> {
+        let rules = parse_rules(
+            "# newsletters stay unread\n\
+             \n\
+             inbox/newsletters/*  select=dirty,new forbid=S\n\
+             todo force=F\n\
+             spam skip\n",
+        )?;
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].pattern, "inbox/newsletters/*");
+        assert_eq!(rules[0].select, vec![Criterion::Dirty, Criterion::New]);
+        assert_eq!(rules[0].forbid, vec!['S']);
+        assert_eq!(rules[1].select, Criterion::ALL.to_vec());
+        assert_eq!(rules[1].force, vec!['F']);
+        assert!(rules[2].skip);
+        assert!(!rules[2].selects(Criterion::Dirty));
+
+        assert!(parse_rules("inbox select=unread").is_err());
+        assert!(parse_rules("inbox force=S1").is_err());
+        assert!(parse_rules("inbox keep").is_err());
+        assert!(parse_rules("inbox/[ skip").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let rule = |pattern: &str| FolderRule {
+            pattern: pattern.to_string(),
+            ..FolderRule::default()
+        };
+        assert!(rule("inbox/*").matches("inbox/lists", "lists"));
+        assert!(!rule("inbox/*").matches("inbox/lists/rust", "rust"));
+        assert!(rule("lists").matches("inbox/lists", "lists"));
+        assert!(rule("list?").matches("archive/lists", "lists"));
+        assert!(!rule("inbox").matches("inbox/lists", "lists"));
+        assert!(FolderRule::default().matches("", "Local Folders"));
+    }
+
+    #[test]
+    fn test_apply_flags() {
+        let rule = FolderRule {
+            force: vec!['F'],
+            forbid: vec!['S'],
+            ..FolderRule::default()
+        };
+        let mut letters = vec!['R', 'S'];
+        rule.apply_flags(&mut letters);
+        assert_eq!(letters, vec!['R', 'F']);
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_fetch_collection_paths(pool: Pool<MySql>) -> Result<()> {
+        let paths = fetch_collection_paths(pool.clone()).await?;
+        assert_eq!(paths[&46], ("".to_string(), "Local Folders".to_string()));
+        assert_eq!(
+            paths[&66],
+            ("inbox/to_be_filed".to_string(), "to_be_filed".to_string())
+        );
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_fetch_todo_items_with_rules(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+        args.rules_path = format!("{}/rules.conf", temp_dir);
+        let mut ctx = RunContext::default();
+
+        std::fs::write(&args.rules_path, "nirwana skip\n")?;
+        ctx.folder_rules = load_folder_rules(pool.clone(), &args).await?;
+        let mut ids: Vec<i64> = fetch_todo_pim_items(pool.clone(), &args, &ctx)
+            .await?
+            .iter()
+            .map(|item| item.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![206, 1207, 50377, 132632]);
+
+        std::fs::write(&args.rules_path, "inbox/temporary select=dirty\n")?;
+        ctx.folder_rules = load_folder_rules(pool.clone(), &args).await?;
+        assert_eq!(
+            fetch_todo_pim_items(pool.clone(), &args, &ctx).await?.len(),
+            7
+        );
+
+        std::fs::write(&args.rules_path, "* skip\n")?;
+        ctx.folder_rules = load_folder_rules(pool.clone(), &args).await?;
+        assert!(fetch_todo_pim_items(pool.clone(), &args, &ctx)
+            .await?
+            .is_empty());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
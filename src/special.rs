@@ -0,0 +1,238 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::{CliArgs, TrashPolicy};
+use crate::process::target_path::{fetch_flag_names, split_mail_name};
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::collections::HashMap;
+
+#[cfg(test)]
+pub(crate) mod test_special;
+
+/// Role of a folder marked by KMail with a `SpecialCollectionAttribute`.
+/// Other roles such as `sent-mail` need no special handling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecialCollection {
+    Inbox,
+    Outbox,
+    Drafts,
+    Templates,
+    Trash,
+}
+
+impl SpecialCollection {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "inbox" => Some(SpecialCollection::Inbox),
+            "outbox" => Some(SpecialCollection::Outbox),
+            "drafts" => Some(SpecialCollection::Drafts),
+            "templates" => Some(SpecialCollection::Templates),
+            "trash" => Some(SpecialCollection::Trash),
+            _ => None,
+        }
+    }
+}
+
+/// Special folders by collection id.
+pub type SpecialCollections = HashMap<i64, SpecialCollection>;
+
+/// Items treated differently because of their special folder.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SpecialTotals {
+    /// Queued items left alone in the Outbox
+    pub queued: usize,
+    /// Items in Drafts given the `D` flag
+    pub drafts: usize,
+    /// Deleted items moved to the Trash
+    pub trash_moved: usize,
+    /// Deleted items purged
+    pub trash_purged: usize,
+}
+
+impl SpecialTotals {
+    pub fn add(&mut self, other: &SpecialTotals) {
+        self.queued += other.queued;
+        self.drafts += other.drafts;
+        self.trash_moved += other.trash_moved;
+        self.trash_purged += other.trash_purged;
+    }
+}
+
+/// Fetch the local collections marked as special folders.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+///
+/// Returns `Result<SpecialCollections>`
+///
+pub async fn fetch_special_collections(pool: Pool<MySql>) -> Result<SpecialCollections> {
+    let rows: Vec<(i64, Option<String>)> = sqlx::query_as(
+        "SELECT `collectionId`, CONVERT(`value`, CHAR)
+        FROM `collectionattributetable`
+        WHERE `type` = 'SpecialCollectionAttribute'
+        AND `collectionId` IN (
+            SELECT id FROM `collectiontable` WHERE `resourceId` = 3
+        )",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, value)| Some((id, SpecialCollection::parse(value?.trim())?)))
+        .collect())
+}
+
+/// Check whether an item waits in the Outbox to be sent by KMail.
+///
+/// # Arguments
+/// - `special`: Special folders by collection id
+/// - `collection_id`: Collection of the item
+/// - `flags`: Flag names of the item
+///
+pub fn is_queued(special: &SpecialCollections, collection_id: i64, flags: &[String]) -> bool {
+    special.get(&collection_id) == Some(&SpecialCollection::Outbox)
+        && flags.iter().any(|f| f.eq_ignore_ascii_case("$QUEUED"))
+}
+
+/// Check whether an item waits in the Outbox, fetching its flags only for
+/// items in the Outbox.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `special`: Special folders by collection id
+/// - `item_id`: Id of the item
+/// - `collection_id`: Collection of the item
+///
+/// Returns `Result<bool>`
+///
+pub async fn is_queued_item(
+    pool: Pool<MySql>,
+    special: &SpecialCollections,
+    item_id: i64,
+    collection_id: i64,
+) -> Result<bool> {
+    if special.get(&collection_id) != Some(&SpecialCollection::Outbox) {
+        return Ok(false);
+    }
+    let flags = fetch_flag_names(item_id, pool).await?;
+    Ok(is_queued(special, collection_id, &flags))
+}
+
+/// Find an item waiting in the Outbox among the items of a mail file or of
+/// a set of duplicates, which must then be left alone as a whole.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `special`: Special folders by collection id
+/// - `items`: Ids and collections of the items
+///
+/// Returns `Result<Option<i64>>` with the id of the first queued item
+///
+pub async fn find_queued_item(
+    pool: Pool<MySql>,
+    special: &SpecialCollections,
+    items: &[(i64, i64)],
+) -> Result<Option<i64>> {
+    for (item_id, collection_id) in items {
+        if is_queued_item(pool.clone(), special, *item_id, *collection_id).await? {
+            return Ok(Some(*item_id));
+        }
+    }
+    Ok(None)
+}
+
+/// Check whether an item is flagged `\DELETED` and the trash policy asks for
+/// moving it to the Trash or purging it.
+pub fn is_trash_item(
+    policy: TrashPolicy,
+    special: &SpecialCollections,
+    collection_id: i64,
+    flags: &[String],
+) -> bool {
+    let deleted = flags.iter().any(|f| f.eq_ignore_ascii_case("\\DELETED"));
+    match policy {
+        TrashPolicy::Keep => false,
+        TrashPolicy::Move => deleted && get_trash_collection(special) != Some(collection_id),
+        TrashPolicy::Purge => deleted,
+    }
+}
+
+/// The local Trash folder, if any.
+pub fn get_trash_collection(special: &SpecialCollections) -> Option<i64> {
+    let mut trash: Vec<i64> = special
+        .iter()
+        .filter(|(_, role)| **role == SpecialCollection::Trash)
+        .map(|(id, _)| *id)
+        .collect();
+    trash.sort();
+    trash.first().copied()
+}
+
+/// Add the `D` flag to the letters of an item in the Drafts folder.
+///
+/// Returns `true` if the flag was missing
+///
+pub fn apply_draft_flag(
+    special: &SpecialCollections,
+    collection_id: i64,
+    letters: &mut Vec<char>,
+) -> bool {
+    if special.get(&collection_id) != Some(&SpecialCollection::Drafts) || letters.contains(&'D') {
+        return false;
+    }
+    letters.push('D');
+    true
+}
+
+/// Check whether moving an item of the Drafts folder from `source` to
+/// `target` gives it the `D` flag.
+pub fn gains_draft_flag(
+    special: &SpecialCollections,
+    collection_id: i64,
+    source: &str,
+    target: &str,
+) -> bool {
+    let has_draft_flag = |path: &str| {
+        split_mail_name(path.rsplit('/').next().unwrap_or(path))
+            .1
+            .contains(&'D')
+    };
+    special.get(&collection_id) == Some(&SpecialCollection::Drafts)
+        && has_draft_flag(target)
+        && !has_draft_flag(source)
+}
+
+/// Print the items handled by each special behaviour, if any.
+pub fn report_special_totals(args: &CliArgs, totals: &SpecialTotals) {
+    let would = if args.dry_run { "would be " } else { "" };
+    if totals.queued > 0 {
+        println!("Outbox: {} queued items left alone.", totals.queued);
+    }
+    if totals.drafts > 0 {
+        println!("Drafts: {} items {}flagged D.", totals.drafts, would);
+    }
+    if totals.trash_moved > 0 {
+        println!(
+            "Trash: {} deleted items {}moved to the trash.",
+            totals.trash_moved, would
+        );
+    }
+    if totals.trash_purged > 0 {
+        println!(
+            "Trash: {} deleted items {}purged.",
+            totals.trash_purged, would
+        );
+    }
+}
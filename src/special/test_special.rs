@@ -0,0 +1,201 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the special-collection handling.
+///
+/// # Test Setup
+///
+/// The fixture marks the local collections 48 `inbox`, 46 `Local Folders`,
+/// 47 `drafts`, 49 `outbox`, 50 `sent-mail`, 51 `templates` and 52 `trash`
+/// with a `SpecialCollectionAttribute`. The database tests create a temporary
+/// mail directory structure by copying test data from `tests/data` to a unique
+/// temporary location.
+///
+/// # Test Cases
+///
+/// - `test_is_queued`: Verifies that only `$QUEUED` items in the Outbox are left alone.
+/// - `test_is_trash_item`: Verifies the `--trash` policies.
+/// - `test_apply_draft_flag`: Verifies that items in Drafts get the `D` flag once
+///   and that only moves adding it are counted.
+/// - `test_fetch_special_collections`: Verifies the special folders of the fixture.
+/// - `test_deleted_item_moved_to_trash`: Verifies that a deleted item in `temporary`
+///   is moved to the trash folder and removed from the database.
+/// - `test_deleted_item_purged`: Verifies that a purged item loses its row and its file.
+///
+mod tests {
+    use crate::cmdline::TrashPolicy;
+    use crate::context::RunContext;
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use crate::process::maildirs::fetch_full_paths;
+    use crate::process::process_single_todo_item;
+    use crate::special::{
+        apply_draft_flag, fetch_special_collections, gains_draft_flag, get_trash_collection,
+        is_queued, is_trash_item, SpecialCollection, SpecialCollections,
+    };
+    use crate::todoitems::TodoPimItem;
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+
+    fn special() -> SpecialCollections {
+        [
+            (47, SpecialCollection::Drafts),
+            (49, SpecialCollection::Outbox),
+            (52, SpecialCollection::Trash),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn names(flags: &[&str]) -> Vec<String> {
+        flags.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_is_queued() {
+        assert!(is_queued(&special(), 49, &names(&["$QUEUED"])));
+        assert!(!is_queued(&special(), 49, &names(&["$SENT"])));
+        assert!(!is_queued(&special(), 48, &names(&["$QUEUED"])));
+    }
+
+    #[test]
+    fn test_is_trash_item() {
+        let deleted = names(&["\\SEEN", "\\DELETED"]);
+        assert!(!is_trash_item(TrashPolicy::Keep, &special(), 48, &deleted));
+        assert!(is_trash_item(TrashPolicy::Move, &special(), 48, &deleted));
+        assert!(!is_trash_item(TrashPolicy::Move, &special(), 52, &deleted));
+        assert!(is_trash_item(TrashPolicy::Purge, &special(), 52, &deleted));
+        assert!(!is_trash_item(
+            TrashPolicy::Purge,
+            &special(),
+            48,
+            &names(&["\\SEEN"])
+        ));
+        assert_eq!(get_trash_collection(&SpecialCollections::new()), None);
+    }
+
+    #[test]
+    fn test_apply_draft_flag() {
+        let special = special();
+        let mut letters = vec!['S'];
+        assert!(apply_draft_flag(&special, 47, &mut letters));
+        assert!(!apply_draft_flag(&special, 47, &mut letters));
+        assert_eq!(letters, vec!['S', 'D']);
+        assert!(!apply_draft_flag(&special, 48, &mut letters));
+        assert!(gains_draft_flag(
+            &special,
+            47,
+            "tmp/1.R1.x:2,S",
+            "cur/1.R1.x:2,DS"
+        ));
+        assert!(!gains_draft_flag(
+            &special,
+            47,
+            "cur/1.R1.x:2,D",
+            "cur/1.R1.x:2,DS"
+        ));
+        assert!(!gains_draft_flag(
+            &special,
+            48,
+            "tmp/1.R1.x:2,S",
+            "cur/1.R1.x:2,DS"
+        ));
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_fetch_special_collections(pool: Pool<MySql>) -> Result<()> {
+        let special = fetch_special_collections(pool.clone()).await?;
+        assert_eq!(special.len(), 6);
+        assert_eq!(special[&48], SpecialCollection::Inbox);
+        assert_eq!(special[&47], SpecialCollection::Drafts);
+        assert_eq!(special[&49], SpecialCollection::Outbox);
+        assert_eq!(special[&52], SpecialCollection::Trash);
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_deleted_item_moved_to_trash(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+        args.trash = TrashPolicy::Move;
+        let ctx = RunContext {
+            special_collections: fetch_special_collections(pool.clone()).await?,
+            ..RunContext::default()
+        };
+        sqlx::query("INSERT INTO `pimitemflagrelation` VALUES (206, 16)")
+            .execute(&pool)
+            .await?;
+
+        let full_paths = fetch_full_paths(pool.clone(), &args).await?;
+        let item = TodoPimItem {
+            id: 206,
+            remote_id: Some("1291727681.2020.4jNSG:2,S".to_string()),
+            collection_id: 388,
+        };
+        let totals =
+            process_single_todo_item(pool.clone(), &item, &full_paths, &args, &ctx).await?;
+        assert_eq!(totals.special.trash_moved, 1);
+        assert_eq!(totals.deleted.items, 1);
+
+        let trash_files: Vec<String> = std::fs::read_dir(format!("{}cur", full_paths[&52]))?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<Result<_>>()?;
+        assert_eq!(trash_files.len(), 1);
+        assert!(trash_files[0].ends_with(":2,ST"), "{:?}", trash_files);
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM `pimitemtable` WHERE `id` = 206")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(row.0, 0);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_deleted_item_purged(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+        args.trash = TrashPolicy::Purge;
+        sqlx::query("INSERT INTO `pimitemflagrelation` VALUES (206, 16)")
+            .execute(&pool)
+            .await?;
+
+        let full_paths = fetch_full_paths(pool.clone(), &args).await?;
+        let item = TodoPimItem {
+            id: 206,
+            remote_id: Some("1291727681.2020.4jNSG:2,S".to_string()),
+            collection_id: 388,
+        };
+        let source = format!("{}new/1291727681.2020.4jNSG:2,S", full_paths[&388]);
+        assert!(std::path::Path::new(&source).exists());
+        let totals = process_single_todo_item(
+            pool.clone(),
+            &item,
+            &full_paths,
+            &args,
+            &RunContext::default(),
+        )
+        .await?;
+        assert_eq!(totals.special.trash_purged, 1);
+        assert_eq!(totals.deleted.items, 1);
+        assert!(!std::path::Path::new(&source).exists());
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM `pimitemtable` WHERE `id` = 206")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(row.0, 0);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
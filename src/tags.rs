@@ -0,0 +1,398 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::dedupe::{body_hash, get_data_path};
+use crate::mail_index::{normalize_message_id, read_message_id};
+use crate::process::maildirs::fetch_full_paths;
+use crate::process::source_path::get_maildir_files;
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sqlx::{FromRow, MySql, Pool};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+#[cfg(test)]
+pub(crate) mod test_tags;
+
+/// Tag of a deleted item waiting to be re-attached to the re-imported item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagRecord {
+    /// Id of the deleted item
+    pub item_id: i64,
+    pub message_id: String,
+    /// Hash of the mail body, see `body_hash`
+    pub content_hash: String,
+    pub tag_gid: String,
+    pub tag_type: String,
+    /// `type` and `value` of the rows in `tagattributetable`
+    pub attributes: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl TagRecord {
+    fn to_line(&self) -> String {
+        let attributes: Vec<String> = self
+            .attributes
+            .iter()
+            .map(|(t, v)| format!("{}:{}", STANDARD.encode(t), STANDARD.encode(v)))
+            .collect();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            self.item_id,
+            self.message_id,
+            self.content_hash,
+            self.tag_gid,
+            self.tag_type,
+            attributes.join(",")
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 6 {
+            return None;
+        }
+        let mut attributes = Vec::new();
+        for attribute in fields[5].split(',').filter(|a| !a.is_empty()) {
+            let (t, v) = attribute.split_once(':')?;
+            attributes.push((STANDARD.decode(t).ok()?, STANDARD.decode(v).ok()?));
+        }
+        Some(TagRecord {
+            item_id: fields[0].parse().ok()?,
+            message_id: fields[1].to_string(),
+            content_hash: fields[2].to_string(),
+            tag_gid: fields[3].to_string(),
+            tag_type: fields[4].to_string(),
+            attributes,
+        })
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct ItemTag {
+    id: i64,
+    gid: String,
+    type_name: Option<String>,
+}
+
+/// Outcome of the search for the item re-imported from a deleted item.
+#[derive(Debug, PartialEq)]
+enum Reimported {
+    Found(i64),
+    Missing,
+    /// No body hash to confirm a candidate, or several candidates match
+    Ambiguous,
+}
+
+#[derive(Debug, FromRow)]
+struct TaggedMail {
+    id: i64,
+    remote_id: Option<String>,
+    collection_id: i64,
+}
+
+/// Directory keeping the tags of deleted items, always with a trailing slash.
+pub fn get_tag_store_path(args: &CliArgs) -> Result<String> {
+    get_data_path(&args.tag_store_path, "tags")
+}
+
+/// Read the tags waiting to be re-attached.
+pub fn load_tag_records(tag_file: &str) -> Result<Vec<TagRecord>> {
+    let Ok(file) = File::open(tag_file) else {
+        return Ok(Vec::new());
+    };
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        records.extend(TagRecord::from_line(&line?));
+    }
+    Ok(records)
+}
+
+/// Replace a file of tag records atomically; an empty list removes it.
+pub fn save_tag_records(tag_file: &str, records: &[TagRecord]) -> Result<()> {
+    if records.is_empty() {
+        if Path::new(tag_file).exists() {
+            std::fs::remove_file(tag_file)?;
+        }
+        return Ok(());
+    }
+    let content: String = records.iter().map(TagRecord::to_line).collect();
+    let tmp_path = format!("{}.tmp", tag_file);
+    std::fs::write(&tmp_path, content)
+        .and_then(|_| std::fs::rename(&tmp_path, tag_file))
+        .map_err(|e| anyhow::anyhow!("Cannot write {}: {}", tag_file, e))?;
+    Ok(())
+}
+
+/// Record the tags of an item before its row is deleted, as deleting the
+/// row cascades through `pimitemtagrelation`. The tags are keyed by the
+/// Message-ID and the body hash of the mail, such that they can be found
+/// again on the item Akonadi creates when re-importing the file. Without a
+/// mail file nothing is re-imported, so the tags are reported as lost
+/// instead of being recorded.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `id`: Pim item id
+/// - `mail_file`: File holding the mail after the move, if any
+///
+/// Returns the number of recorded tags
+///
+pub async fn record_item_tags(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    id: i64,
+    mail_file: Option<&str>,
+) -> Result<usize> {
+    let tags = sqlx::query_as::<_, ItemTag>(
+        "SELECT `tagtable`.`id`,
+            CONVERT(`tagtable`.`gid`, CHAR) AS `gid`,
+            CONVERT(`tagtypetable`.`name`, CHAR) AS `type_name`
+        FROM `pimitemtagrelation`
+        JOIN `tagtable` ON `pimitemtagrelation`.`Tag_id` = `tagtable`.`id`
+        LEFT JOIN `tagtypetable` ON `tagtable`.`typeId` = `tagtypetable`.`id`
+        WHERE `pimitemtagrelation`.`PimItem_id` = ?",
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await?;
+    if tags.is_empty() {
+        return Ok(0);
+    }
+    let Some(mail_file) = mail_file.filter(|file| Path::new(file).is_file()) else {
+        println!(
+            "Item ID {}: no mail file to re-import, {} tags are lost",
+            id,
+            tags.len()
+        );
+        return Ok(0);
+    };
+
+    let gid: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT CONVERT(`gid`, CHAR) FROM `pimitemtable` WHERE `id` = ?")
+            .bind(id)
+            .fetch_optional(&pool)
+            .await?;
+    let message_id = match gid.and_then(|(gid,)| gid).map(|g| normalize_message_id(&g)) {
+        Some(gid) if !gid.is_empty() => gid,
+        _ => read_message_id(mail_file)?.unwrap_or_default(),
+    };
+    let content_hash = body_hash(mail_file)?;
+
+    let mut lines = String::new();
+    for tag in &tags {
+        let attributes: Vec<(Vec<u8>, Option<Vec<u8>>)> =
+            sqlx::query_as("SELECT `type`, `value` FROM `tagattributetable` WHERE `tagId` = ?")
+                .bind(tag.id)
+                .fetch_all(&pool)
+                .await?;
+        let record = TagRecord {
+            item_id: id,
+            message_id: message_id.clone(),
+            content_hash: content_hash.clone(),
+            tag_gid: tag.gid.clone(),
+            tag_type: tag.type_name.clone().unwrap_or_default(),
+            attributes: attributes
+                .into_iter()
+                .map(|(t, v)| (t, v.unwrap_or_default()))
+                .collect(),
+        };
+        lines.push_str(&record.to_line());
+    }
+    if args.verbose {
+        println!(
+            "Item ID {}: recorded {} tags for re-attaching",
+            id,
+            tags.len()
+        );
+    }
+
+    let tag_path = get_tag_store_path(args)?;
+    std::fs::create_dir_all(&tag_path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("{}pending.tsv", tag_path))?;
+    file.write_all(lines.as_bytes())?;
+    file.sync_all()?;
+    Ok(tags.len())
+}
+
+/// Find the id of a tag by its gid, re-creating the tag if it is gone.
+async fn find_or_create_tag(pool: Pool<MySql>, record: &TagRecord) -> Result<i64> {
+    let tag_id: Option<(i64,)> = sqlx::query_as("SELECT `id` FROM `tagtable` WHERE `gid` = ?")
+        .bind(&record.tag_gid)
+        .fetch_optional(&pool)
+        .await?;
+    if let Some((tag_id,)) = tag_id {
+        return Ok(tag_id);
+    }
+    let tag_id = sqlx::query(
+        "INSERT INTO `tagtable` (`gid`, `typeId`)
+        SELECT ?, COALESCE((SELECT `id` FROM `tagtypetable` WHERE `name` = ?), 1)",
+    )
+    .bind(&record.tag_gid)
+    .bind(&record.tag_type)
+    .execute(&pool)
+    .await?
+    .last_insert_id() as i64;
+    for (attribute_type, value) in &record.attributes {
+        sqlx::query("INSERT INTO `tagattributetable` (`tagId`, `type`, `value`) VALUES (?, ?, ?)")
+            .bind(tag_id)
+            .bind(attribute_type)
+            .bind(value)
+            .execute(&pool)
+            .await?;
+    }
+    Ok(tag_id)
+}
+
+/// Find the re-imported item of a tag record: an item created after the
+/// deleted one, with the same Message-ID (or without one), whose file has
+/// the same body hash. Without a body hash no candidate can be confirmed.
+async fn find_reimported_item(
+    pool: Pool<MySql>,
+    record: &TagRecord,
+    full_paths: &HashMap<i64, String>,
+) -> Result<Reimported> {
+    if record.content_hash.is_empty() {
+        return Ok(Reimported::Ambiguous);
+    }
+    let candidates = sqlx::query_as::<_, TaggedMail>(
+        "SELECT `id`,
+            CONVERT(`remoteId`, CHAR) AS `remote_id`,
+            `collectionId` AS `collection_id`
+        FROM `pimitemtable`
+        WHERE `mimeTypeId` = 2 AND `id` > ?
+        AND COALESCE(CONVERT(`gid`, CHAR), '') IN (?, ?)
+        ORDER BY `id`",
+    )
+    .bind(record.item_id)
+    .bind(&record.message_id)
+    .bind(format!("<{}>", record.message_id))
+    .fetch_all(&pool)
+    .await?;
+    let mut matches = Vec::new();
+    for candidate in candidates {
+        let (Some(rid), Some(folder)) = (
+            candidate.remote_id.as_ref(),
+            full_paths.get(&candidate.collection_id),
+        ) else {
+            continue;
+        };
+        for file in get_maildir_files(folder, rid)? {
+            if body_hash(&file)? == record.content_hash {
+                matches.push(candidate.id);
+                break;
+            }
+        }
+    }
+    Ok(match matches[..] {
+        [] => Reimported::Missing,
+        [id] => Reimported::Found(id),
+        _ => Reimported::Ambiguous,
+    })
+}
+
+/// Re-attach the tags recorded before deleting items to the items Akonadi
+/// created when re-importing their files. Tags whose item is not found or
+/// not unique stay pending for the next `reattach-tags` and are written to `unmatched.tsv` in the
+/// tag store directory.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+///
+/// Returns `Result<()>`
+///
+pub async fn reattach_tags(pool: Pool<MySql>, args: &CliArgs) -> Result<()> {
+    let tag_path = get_tag_store_path(args)?;
+    let pending_file = format!("{}pending.tsv", tag_path);
+    let records = load_tag_records(&pending_file)?;
+    if records.is_empty() {
+        return Ok(());
+    }
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let mut unmatched = Vec::new();
+    let mut ambiguous = 0;
+    let mut attached = 0;
+
+    for record in records {
+        let item_id = match find_reimported_item(pool.clone(), &record, &full_paths).await? {
+            Reimported::Found(item_id) => item_id,
+            Reimported::Missing => {
+                println!(
+                    "Tag {} of deleted item ID {} (Message-ID <{}>) not found on a re-imported item.",
+                    record.tag_gid, record.item_id, record.message_id
+                );
+                unmatched.push(record);
+                continue;
+            }
+            Reimported::Ambiguous => {
+                println!(
+                    "Tag {} of deleted item ID {} (Message-ID <{}>) is ambiguous, not re-attached.",
+                    record.tag_gid, record.item_id, record.message_id
+                );
+                ambiguous += 1;
+                unmatched.push(record);
+                continue;
+            }
+        };
+        if args.verbose || args.dry_run {
+            println!(
+                "{} tag {} of deleted item ID {} to item ID {}",
+                if args.dry_run {
+                    "Would attach"
+                } else {
+                    "Attaching"
+                },
+                record.tag_gid,
+                record.item_id,
+                item_id
+            );
+        }
+        if !args.dry_run {
+            let tag_id = find_or_create_tag(pool.clone(), &record).await?;
+            sqlx::query(
+                "INSERT IGNORE INTO `pimitemtagrelation` (`PimItem_id`, `Tag_id`) VALUES (?, ?)",
+            )
+            .bind(item_id)
+            .bind(tag_id)
+            .execute(&pool)
+            .await?;
+        }
+        attached += 1;
+    }
+
+    if !args.dry_run {
+        save_tag_records(&pending_file, &unmatched)?;
+        save_tag_records(&format!("{}unmatched.tsv", tag_path), &unmatched)?;
+    }
+    println!(
+        "{} tags {}, {} not matched yet, {} ambiguous.",
+        attached,
+        if args.dry_run {
+            "would be re-attached"
+        } else {
+            "re-attached"
+        },
+        unmatched.len() - ambiguous,
+        ambiguous
+    );
+    Ok(())
+}
@@ -0,0 +1,197 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for preserving tags across the delete/re-import cycle.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location, which also holds the tag store.
+/// The tag `important` with a name attribute is put on item 1322, whose file
+/// is `to_be_filed/cur/1330783242.R2038.sirius:2,S`.
+///
+/// # Test Cases
+///
+/// - `test_tag_record_line`: Verifies writing and parsing a tag record.
+/// - `test_tag_records_file`: Verifies saving and loading the tag records.
+/// - `test_preserve_tags`: Verifies that deleting item 1322 records its tag and that
+///   the tag is re-attached to the row created by the re-import.
+/// - `test_tags_without_mail_file`: Verifies that the tags of an item deleted
+///   without a mail file are not recorded.
+/// - `test_reattach_tags_ambiguous`: Verifies that a record without body hash and
+///   an older row with the same Message-ID are not matched.
+///
+mod tests {
+    use crate::dedupe::body_hash;
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use crate::process::execute::update_akonadi_db;
+    use crate::tags::{
+        get_tag_store_path, load_tag_records, reattach_tags, save_tag_records, TagRecord,
+    };
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+
+    const MAIL: &str = "local_mail/.inbox.directory/to_be_filed/cur/1330783242.R2038.sirius:2,S";
+    const MESSAGE_ID: &str =
+        "OFDCFBA1D1.99586397-ONC125782D.0034BE63-C125782D.0034DFDB@LocalDomain";
+
+    fn record() -> TagRecord {
+        TagRecord {
+            item_id: 1322,
+            message_id: MESSAGE_ID.to_string(),
+            content_hash: "0123".to_string(),
+            tag_gid: "important".to_string(),
+            tag_type: "PLAIN".to_string(),
+            attributes: vec![(b"NAME".to_vec(), "Wichtig\t!".as_bytes().to_vec())],
+        }
+    }
+
+    #[test]
+    fn test_tag_record_line() {
+        let line = record().to_line();
+        assert_eq!(line.matches('\t').count(), 5);
+        assert_eq!(TagRecord::from_line(line.trim_end()), Some(record()));
+        assert_eq!(TagRecord::from_line("1322\tid\thash"), None);
+    }
+
+    #[test]
+    fn test_tag_records_file() -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let tag_file = format!("{}/pending.tsv", temp_dir);
+        assert!(load_tag_records(&tag_file)?.is_empty());
+
+        save_tag_records(&tag_file, &[record(), record()])?;
+        assert_eq!(load_tag_records(&tag_file)?, vec![record(), record()]);
+        save_tag_records(&tag_file, &[])?;
+        assert!(!Path::new(&tag_file).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_preserve_tags(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, false);
+        sqlx::query("INSERT INTO tagtable (id, gid, typeId) VALUES (7, 'important', 1)")
+            .execute(&pool)
+            .await?;
+        sqlx::query(
+            "INSERT INTO tagattributetable (tagId, type, value) VALUES (7, 'NAME', 'Wichtig')",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query("INSERT INTO pimitemtagrelation (PimItem_id, Tag_id) VALUES (1322, 7)")
+            .execute(&pool)
+            .await?;
+
+        let mail_file = format!("{}/{}", temp_dir, MAIL);
+        update_akonadi_db(pool.clone(), &args, 1322, Some(&mail_file)).await?;
+        let pending_file = format!("{}pending.tsv", get_tag_store_path(&args)?);
+        let records = load_tag_records(&pending_file)?;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message_id, MESSAGE_ID);
+        assert_eq!(records[0].tag_gid, "important");
+        assert_eq!(
+            records[0].attributes,
+            vec![(b"NAME".to_vec(), b"Wichtig".to_vec())]
+        );
+
+        // Nothing re-imported yet, the tag stays pending
+        reattach_tags(pool.clone(), &args).await?;
+        assert_eq!(load_tag_records(&pending_file)?.len(), 1);
+
+        // Simulate the re-import by the maildir resource
+        let new_id = sqlx::query(
+            "INSERT INTO pimitemtable (remoteId, gid, collectionId, mimeTypeId, dirty)
+            VALUES ('1330783242.R2038.sirius:2,S', ?, 66, 2, 0)",
+        )
+        .bind(MESSAGE_ID)
+        .execute(&pool)
+        .await?
+        .last_insert_id();
+        reattach_tags(pool.clone(), &args).await?;
+        let tagged: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pimitemtagrelation WHERE PimItem_id = ? AND Tag_id = 7",
+        )
+        .bind(new_id)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(tagged, 1);
+        assert!(!Path::new(&pending_file).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_tags_without_mail_file(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, false);
+        sqlx::query("INSERT INTO tagtable (id, gid, typeId) VALUES (7, 'important', 1)")
+            .execute(&pool)
+            .await?;
+        sqlx::query("INSERT INTO pimitemtagrelation (PimItem_id, Tag_id) VALUES (1322, 7)")
+            .execute(&pool)
+            .await?;
+
+        let missing = format!("{}/{}.missing", temp_dir, MAIL);
+        update_akonadi_db(pool.clone(), &args, 1322, Some(&missing)).await?;
+        let pending_file = format!("{}pending.tsv", get_tag_store_path(&args)?);
+        assert!(!Path::new(&pending_file).exists());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_reattach_tags_ambiguous(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let args = create_test_cli_args(&temp_dir, false);
+        let tag_path = get_tag_store_path(&args)?;
+        std::fs::create_dir_all(&tag_path)?;
+        let pending_file = format!("{}pending.tsv", tag_path);
+        sqlx::query("UPDATE pimitemtable SET gid = ? WHERE id = 1322")
+            .bind(MESSAGE_ID)
+            .execute(&pool)
+            .await?;
+        let hash = body_hash(&format!("{}/{}", temp_dir, MAIL))?;
+
+        // Item 1322 was created before the deleted item 50000
+        let older = TagRecord {
+            item_id: 50000,
+            content_hash: hash,
+            ..record()
+        };
+        // Without a body hash item 1322 cannot be confirmed
+        let unhashed = TagRecord {
+            item_id: 1000,
+            content_hash: String::new(),
+            ..record()
+        };
+        save_tag_records(&pending_file, &[older.clone(), unhashed.clone()])?;
+        reattach_tags(pool.clone(), &args).await?;
+
+        let tagged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pimitemtagrelation")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(tagged, 0);
+        assert_eq!(load_tag_records(&pending_file)?, vec![older, unhashed]);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::header::MailHeader;
+use crate::impact::{report_delete_impact, report_delete_totals, DeleteImpact};
+use crate::mail_index::read_message_id;
+use crate::process::execute::{delete_file, move_mail, update_akonadi_db};
+use crate::process::maildirs::fetch_full_paths;
+use crate::process::source_path::get_maildir_files;
+use crate::process::target_path::{
+    assign_keyword_letters, build_mail_info, create_new_mail_name, get_flag_map, get_mail_flags,
+    MailFlags,
+};
+use crate::special::{fetch_special_collections, find_queued_item};
+use crate::todoitems::{fetch_items_by_gid, TodoPimItem};
+use anyhow::Result;
+use sqlx::{MySql, Pool};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+pub(crate) mod test_tmp_files;
+
+/// State of a file in a `tmp` directory.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TmpFileState {
+    /// Modified within the age threshold, possibly still being delivered
+    Recent,
+    /// Complete message not yet delivered
+    Complete,
+    /// Complete message whose item is already backed by a file in `new` or `cur`
+    Delivered,
+    /// Truncated or unparseable content
+    Incomplete,
+}
+
+#[derive(Debug)]
+pub struct TmpFile {
+    pub path: String,
+    pub collection_id: i64,
+    pub state: TmpFileState,
+    /// Items of the collection carrying the Message-ID of the file
+    pub items: Vec<TodoPimItem>,
+}
+
+/// Check whether a file holds a complete message, i.e. a mail header block
+/// terminated by an empty line and a body terminated by a line break.
+pub fn is_complete_mail(path: &str) -> Result<bool> {
+    let data = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read mail file: {}: {}", path, e))?;
+    if !MailHeader::parse(&data).is_mail() {
+        return Ok(false);
+    }
+    let has_body =
+        data.windows(2).any(|w| w == b"\n\n") || data.windows(4).any(|w| w == b"\r\n\r\n");
+    Ok(has_body && data.ends_with(b"\n"))
+}
+
+/// Check whether a file name was given by a staging step instead of a
+/// maildir delivery, i.e. a `tmp_db_<uuid>` payload or a bare UUID.
+pub fn is_staging_name(name: &str) -> bool {
+    let uuid = name.strip_prefix("tmp_db_").unwrap_or(name);
+    uuid::Uuid::parse_str(uuid).is_ok()
+}
+
+/// Latest modification time of files older than `min_age_hours`; ages
+/// beyond the UNIX epoch are clamped to it.
+pub fn get_age_threshold(min_age_hours: u64) -> SystemTime {
+    SystemTime::now()
+        .checked_sub(Duration::from_secs(min_age_hours.saturating_mul(3600)))
+        .unwrap_or(UNIX_EPOCH)
+        .max(UNIX_EPOCH)
+}
+
+/// Classify the files in the `tmp` directories of all local folders.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `min_age_hours`: Files modified more recently are left alone
+///
+/// Returns `Result<Vec<TmpFile>>`
+///
+pub async fn scan_tmp_files(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    min_age_hours: u64,
+) -> Result<Vec<TmpFile>> {
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let mut collections: Vec<(&i64, &String)> = full_paths.iter().collect();
+    collections.sort_by(|a, b| a.1.cmp(b.1));
+    let threshold = get_age_threshold(min_age_hours);
+
+    let mut files = Vec::new();
+    for (collection_id, path) in collections {
+        let Ok(entries) = std::fs::read_dir(format!("{}tmp", path)) else {
+            continue;
+        };
+        let mut entries: Vec<std::fs::DirEntry> = entries.filter_map(|e| e.ok()).collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            if !entry.file_type().is_ok_and(|t| t.is_file()) {
+                continue;
+            }
+            let file_path = entry.path().to_string_lossy().to_string();
+            // Files may be delivered or deleted by Akonadi meanwhile
+            let modified = match entry.metadata().and_then(|m| m.modified()) {
+                Ok(modified) => modified,
+                Err(e) => {
+                    eprintln!("Skipping {}: {}", file_path, e);
+                    continue;
+                }
+            };
+            let mut items = Vec::new();
+            let state = if modified > threshold {
+                TmpFileState::Recent
+            } else if !is_complete_mail(&file_path)? {
+                TmpFileState::Incomplete
+            } else {
+                if let Some(message_id) = read_message_id(&file_path)? {
+                    items = fetch_items_by_gid(pool.clone(), *collection_id, &message_id).await?;
+                }
+                let mut delivered = false;
+                for rid in items.iter().filter_map(|i| i.remote_id.as_ref()) {
+                    delivered |= !get_maildir_files(path, rid)?.is_empty();
+                }
+                if delivered {
+                    TmpFileState::Delivered
+                } else {
+                    TmpFileState::Complete
+                }
+            };
+            files.push(TmpFile {
+                path: file_path,
+                collection_id: *collection_id,
+                state,
+                items,
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// Clean up the `tmp` directories of all local folders. Files untouched for
+/// longer than the threshold are garbage according to the maildir spec.
+/// - Complete messages are delivered into `new`, or into `cur` if a matching
+///   item carries flags. The matching items are removed from the database,
+///   such that Akonadi imports the delivered file with its flags. Staging
+///   files named `tmp_db_<uuid>` or `<uuid>` get a new maildir name.
+/// - Incomplete files and copies of already delivered messages are reported
+///   and only removed if requested.
+/// - Messages of items queued in the Outbox stay where they are.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `min_age_hours`: Files modified more recently are left alone
+/// - `remove`: Remove incomplete and already delivered files
+///
+/// Returns `Result<()>`
+///
+pub async fn clean_up_tmp_files(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    min_age_hours: u64,
+    remove: bool,
+) -> Result<()> {
+    let mut deleted = DeleteImpact::default();
+    let dry_run_msg_would = if args.dry_run { "Would move" } else { "Moving" };
+    let dry_run_msg_delete = if args.dry_run {
+        "Would delete"
+    } else {
+        "Deleting"
+    };
+    let full_paths = fetch_full_paths(pool.clone(), args).await?;
+    let flag_map = get_flag_map(args)?;
+    let files = scan_tmp_files(pool.clone(), args, min_age_hours).await?;
+    let special = fetch_special_collections(pool.clone()).await?;
+    let (mut recent, mut delivered, mut garbage, mut removed) = (0, 0, 0, 0);
+
+    for file in &files {
+        match file.state {
+            TmpFileState::Recent => {
+                if args.verbose {
+                    println!("Leaving recent file {}", file.path);
+                }
+                recent += 1;
+            }
+            TmpFileState::Complete => {
+                let Some(folder) = full_paths.get(&file.collection_id) else {
+                    continue;
+                };
+                let items: Vec<(i64, i64)> = file
+                    .items
+                    .iter()
+                    .map(|item| (item.id, item.collection_id))
+                    .collect();
+                if let Some(id) = find_queued_item(pool.clone(), &special, &items).await? {
+                    println!(
+                        "Leaving complete message {} of queued item ID {} in the outbox",
+                        file.path, id
+                    );
+                    continue;
+                }
+                let mut flags = MailFlags::default();
+                for item in &file.items {
+                    let item_flags = get_mail_flags(item.id, pool.clone(), &flag_map).await?;
+                    flags.letters.extend(item_flags.letters);
+                    flags.dropped.extend(item_flags.dropped);
+                }
+                assign_keyword_letters(&mut flags, folder, args)?;
+                let mail_info = build_mail_info(flags.letters);
+                let cur_new_name = if mail_info.is_empty() { "new" } else { "cur" };
+                let name = file.path.rsplit('/').next().unwrap_or(&file.path);
+                let mut target = format!("{}{}/{}{}", folder, cur_new_name, name, mail_info);
+                if name.contains(':') || is_staging_name(name) || Path::new(&target).exists() {
+                    let item_id = file.items.first().map(|item| item.id);
+                    let name =
+                        create_new_mail_name(pool.clone(), &file.path, folder, item_id).await?;
+                    target = format!("{}{}/{}{}", folder, cur_new_name, name, mail_info);
+                }
+                println!("Delivering complete message {}", file.path);
+                if args.verbose || args.dry_run {
+                    println!("  {}", MailHeader::read(&file.path)?.summary());
+                    println!("  {} {} to {}", dry_run_msg_would, file.path, target);
+                }
+                if !args.dry_run {
+                    if let Some(moved) = move_mail(pool.clone(), args, &file.path, &target).await? {
+                        for item in &file.items {
+                            deleted.add(
+                                &update_akonadi_db(pool.clone(), args, item.id, Some(&moved))
+                                    .await?,
+                            );
+                        }
+                    }
+                } else {
+                    for item in &file.items {
+                        deleted.add(&report_delete_impact(pool.clone(), args, item.id).await?);
+                    }
+                }
+                delivered += 1;
+            }
+            TmpFileState::Delivered | TmpFileState::Incomplete => {
+                if file.state == TmpFileState::Delivered {
+                    println!("Message already delivered: {}", file.path);
+                } else {
+                    println!("Incomplete message: {}", file.path);
+                }
+                garbage += 1;
+                if !remove {
+                    continue;
+                }
+                if args.verbose || args.dry_run {
+                    println!("  {} {}", dry_run_msg_delete, file.path);
+                }
+                if !args.dry_run {
+                    delete_file(&file.path)?;
+                }
+                removed += 1;
+            }
+        }
+    }
+
+    println!(
+        "{} files in tmp: {} recent, {} complete messages {}, {} incomplete or already delivered, {} {}.",
+        files.len(),
+        recent,
+        delivered,
+        if args.dry_run { "would be delivered" } else { "delivered" },
+        garbage,
+        removed,
+        if args.dry_run { "would be removed" } else { "removed" }
+    );
+    report_delete_totals(args, &deleted);
+    Ok(())
+}
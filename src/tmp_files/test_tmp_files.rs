@@ -0,0 +1,212 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the clean up of `tmp` directories.
+///
+/// # Test Setup
+///
+/// The tests create a temporary mail directory structure by copying test data from
+/// `tests/data` to a unique temporary location. Four files are added to `nirwana/tmp`:
+/// the payload of item 50643 whose item has no file yet, a copy of the already
+/// delivered mail of item 50628, an incomplete message and a recent file. All but the
+/// recent file are aged beyond the threshold.
+///
+/// # Test Cases
+///
+/// - `test_get_age_threshold`: Verifies that huge ages are clamped to the UNIX epoch.
+/// - `test_is_staging_name`: Verifies that staging names are told apart from maildir names.
+/// - `test_scan_tmp_files`: Verifies the classification of the files.
+/// - `test_clean_up_tmp_files`: Verifies that the complete message is delivered with
+///   the flags of its item and that incomplete files are kept.
+/// - `test_clean_up_tmp_files_staging_name`: Verifies that a `tmp_db_` staging file is
+///   delivered under a new maildir name.
+/// - `test_clean_up_tmp_files_remove`: Verifies that incomplete and already delivered
+///   files are removed on request, but recent files are kept.
+/// - `test_clean_up_tmp_files_queued`: Verifies that the message of a queued Outbox
+///   item stays in `tmp`.
+///
+mod tests {
+    use crate::mockup::{
+        create_test_cli_args, item_present, queue_in_outbox, setup_tmp_mail_dir,
+        teardown_tmp_mail_dir,
+    };
+    use crate::tmp_files::{
+        clean_up_tmp_files, get_age_threshold, is_staging_name, scan_tmp_files, TmpFileState,
+    };
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+    use std::path::Path;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    const FOLDER: &str = "local_mail/.inbox.directory/nirwana";
+    const COMPLETE: &str = "tmp/1767100000.R1.helios";
+    const DELIVERED: &str = "tmp/1767111571664.R999.helios";
+    const INCOMPLETE: &str = "tmp/1767100001.R2.helios";
+    const RECENT: &str = "tmp/1767100002.R3.helios";
+
+    /// Helper function adding the files to `nirwana/tmp` and aging them.
+    fn add_tmp_files(temp_dir: &str) -> Result<String> {
+        let folder = format!("{}/{}", temp_dir, FOLDER);
+        std::fs::create_dir_all(format!("{}/tmp", folder))?;
+        std::fs::copy(
+            format!("{}/file_db_data/57/152657_r0", temp_dir),
+            format!("{}/{}", folder, COMPLETE),
+        )?;
+        std::fs::copy(
+            format!("{}/new/1767111571664.R424.helios", folder),
+            format!("{}/{}", folder, DELIVERED),
+        )?;
+        for file in [INCOMPLETE, RECENT] {
+            std::fs::write(format!("{}/{}", folder, file), "From: a@b\nSubject: Cut o")?;
+        }
+        let old = SystemTime::now() - Duration::from_secs(48 * 3600);
+        for file in [COMPLETE, DELIVERED, INCOMPLETE] {
+            std::fs::File::options()
+                .write(true)
+                .open(format!("{}/{}", folder, file))?
+                .set_modified(old)?;
+        }
+        Ok(folder)
+    }
+
+    #[test]
+    fn test_get_age_threshold() {
+        assert_eq!(get_age_threshold(u64::MAX), UNIX_EPOCH);
+        assert_eq!(get_age_threshold(24 * 365 * 100_000), UNIX_EPOCH);
+        assert!(get_age_threshold(1) < SystemTime::now() - Duration::from_secs(3599));
+    }
+
+    #[test]
+    fn test_is_staging_name() {
+        assert!(is_staging_name(
+            "tmp_db_1b4e28ba-2fa1-11d2-883f-0016d3cca427"
+        ));
+        assert!(is_staging_name("1b4e28ba-2fa1-11d2-883f-0016d3cca427"));
+        assert!(!is_staging_name("1767100000.R1.helios"));
+        assert!(!is_staging_name("tmp_db_1767100000.R1.helios"));
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql", "../../tests/fixtures/gid.sql"))]
+    async fn test_scan_tmp_files(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        add_tmp_files(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, true);
+
+        let files = scan_tmp_files(pool.clone(), &args, 36).await?;
+        let states: Vec<(String, TmpFileState)> = files
+            .iter()
+            .map(|f| {
+                (
+                    f.path.rsplit('/').next().unwrap().to_string(),
+                    f.state.clone(),
+                )
+            })
+            .collect();
+        let expected: Vec<(String, TmpFileState)> = [
+            (COMPLETE, TmpFileState::Complete),
+            (INCOMPLETE, TmpFileState::Incomplete),
+            (RECENT, TmpFileState::Recent),
+            (DELIVERED, TmpFileState::Delivered),
+        ]
+        .into_iter()
+        .map(|(f, s)| (f.trim_start_matches("tmp/").to_string(), s))
+        .collect();
+        assert_eq!(states, expected);
+        assert_eq!(files[0].items.len(), 1);
+        assert_eq!(files[0].items[0].id, 50643);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql", "../../tests/fixtures/gid.sql"))]
+    async fn test_clean_up_tmp_files(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let folder = add_tmp_files(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        clean_up_tmp_files(pool.clone(), &args, 36, false).await?;
+
+        // \SEEN of item 50643 moves the message to cur
+        assert!(Path::new(&format!("{}/cur/1767100000.R1.helios:2,S", folder)).is_file());
+        assert!(!Path::new(&format!("{}/{}", folder, COMPLETE)).exists());
+        assert!(!item_present(pool.clone(), 50643).await?);
+        for file in [DELIVERED, INCOMPLETE, RECENT] {
+            assert!(Path::new(&format!("{}/{}", folder, file)).is_file());
+        }
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql", "../../tests/fixtures/gid.sql"))]
+    async fn test_clean_up_tmp_files_staging_name(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let folder = add_tmp_files(&temp_dir)?;
+        let staging = format!("{}/tmp/tmp_db_1b4e28ba-2fa1-11d2-883f-0016d3cca427", folder);
+        std::fs::rename(format!("{}/{}", folder, COMPLETE), &staging)?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        clean_up_tmp_files(pool.clone(), &args, 36, false).await?;
+
+        assert!(!Path::new(&staging).exists());
+        let delivered: Vec<String> = std::fs::read_dir(format!("{}/cur", folder))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(":2,S"))
+            .collect();
+        assert_eq!(delivered.len(), 1);
+        assert!(!delivered[0].starts_with("tmp_db_"));
+        assert!(!item_present(pool.clone(), 50643).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql", "../../tests/fixtures/gid.sql"))]
+    async fn test_clean_up_tmp_files_remove(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let folder = add_tmp_files(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+
+        clean_up_tmp_files(pool.clone(), &args, 36, true).await?;
+
+        for file in [DELIVERED, INCOMPLETE] {
+            assert!(!Path::new(&format!("{}/{}", folder, file)).exists());
+        }
+        assert!(Path::new(&format!("{}/{}", folder, RECENT)).is_file());
+        assert!(item_present(pool.clone(), 50628).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql", "../../tests/fixtures/gid.sql"))]
+    async fn test_clean_up_tmp_files_queued(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let folder = add_tmp_files(&temp_dir)?;
+        let args = create_test_cli_args(&temp_dir, false);
+        queue_in_outbox(pool.clone(), 394, &[50643]).await?;
+
+        clean_up_tmp_files(pool.clone(), &args, 36, false).await?;
+
+        assert!(Path::new(&format!("{}/{}", folder, COMPLETE)).is_file());
+        assert!(!Path::new(&format!("{}/cur/1767100000.R1.helios:2,S", folder)).exists());
+        assert!(item_present(pool.clone(), 50643).await?);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
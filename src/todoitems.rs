@@ -13,15 +13,22 @@
 // limitations under the License.
 
 use crate::cmdline::CliArgs;
+use crate::context::RunContext;
+use crate::rules::Criterion;
 use anyhow::Result;
+use regex::Regex;
 use sqlx::QueryBuilder;
 use sqlx::{FromRow, MySql, Pool};
+use std::collections::{HashMap, HashSet};
 
 pub(crate) mod new_mails;
+pub(crate) mod selection;
 #[cfg(test)]
 pub(crate) mod test_db_error;
 #[cfg(test)]
 pub(crate) mod test_num_items;
+#[cfg(test)]
+pub(crate) mod test_selection;
 
 #[derive(Debug, FromRow)]
 pub struct TodoPimItem {
@@ -30,63 +37,252 @@ pub struct TodoPimItem {
     pub collection_id: i64,
 }
 
-pub async fn fetch_todo_pim_items(pool: Pool<MySql>, args: &CliArgs) -> Result<Vec<TodoPimItem>> {
-    // Build the query starting with mails that have `Id >= args.min_id`
-    // and are marked as dirty or new
-    let mut query_builder = QueryBuilder::new(
-        "SELECT `id`,
-            CONVERT(`remoteId`, CHAR) AS `remote_id`,
-            `collectionId` AS `collection_id`
-        FROM `pimitemtable`
-        WHERE `mimeTypeId` = 2
-        AND `id` >= ",
-    );
+/// Number of message IDs bound by a single query, well below the limit of
+/// 65535 placeholders of a prepared statement.
+const ID_CHUNK: usize = 10_000;
 
-    query_builder.push_bind(args.min_id);
-    query_builder.push(" AND (`dirty` = 1 OR `remoteId` NOT LIKE '%:2,%S'");
+/// Fetch the items that potentially need a change. Each folder rule
+/// decides which selection criteria apply to the items of its folders,
+/// see `rules::Criterion`; without a rules file all criteria apply to all
+/// local folders. The selection options such as `--folder` or `--since`
+/// narrow the result further, see `selection::push_selection`. The
+/// `--remote-id-regex` is matched here with the same syntax it was checked
+/// with, not by MySQL's `REGEXP`.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `args`: Command line arguments
+/// - `ctx`: Folder rules and message IDs of the run
+///
+/// Returns `Result<Vec<TodoPimItem>>`
+///
+pub async fn fetch_todo_pim_items(
+    pool: Pool<MySql>,
+    args: &CliArgs,
+    ctx: &RunContext,
+) -> Result<Vec<TodoPimItem>> {
+    if ctx.item_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+        return Ok(Vec::new());
+    }
+    let folder_ids = match &args.folder {
+        Some(folder) => Some(selection::fetch_subtree_collections(pool.clone(), folder).await?),
+        None => None,
+    };
+    // Collections selected by each criterion, `None` for all local collections
+    let selected = |criterion: Criterion| -> Option<Vec<i64>> {
+        if ctx.folder_rules.is_empty() {
+            return None;
+        }
+        let mut ids: Vec<i64> = ctx
+            .folder_rules
+            .iter()
+            .filter(|(_, rule)| rule.selects(criterion))
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort();
+        Some(ids)
+    };
+    let mail_list = if selected(Criterion::New).is_some_and(|ids| ids.is_empty()) {
+        Vec::new()
+    } else {
+        new_mails::find_new_mail_files(pool.clone(), args).await?
+    };
 
-    // Include items flagged as `\ANSWERED` but not marked as replied
-    // These items also need to be processed and the flag
-    // changed to replied after moving.
-    query_builder.push(
-        " OR (
-        `id` IN (SELECT pimItem_Id
-                 FROM `pimitemflagrelation`
-                 WHERE `flag_Id` IN (SELECT `id`
-                                     FROM `flagtable`
-                                     WHERE `name` LIKE '%ANSWERED'))
-        AND `remoteId` NOT LIKE '%:2%RS')",
-    );
+    let remote_id_regex = match &args.remote_id_regex {
+        Some(regex) => Some(Regex::new(regex)?),
+        None => None,
+    };
+    // Each query binds at most `ID_CHUNK` message IDs of `--ids-from`
+    let chunks: Vec<Option<&[i64]>> = match &ctx.item_ids {
+        Some(ids) => ids.chunks(ID_CHUNK).map(Some).collect(),
+        None => vec![None],
+    };
+    let mut query: Vec<TodoPimItem> = Vec::new();
+    for chunk in chunks {
+        // Build the query starting with mails that have `Id >= args.min_id`
+        let mut query_builder = QueryBuilder::new(
+            "SELECT `id`,
+                CONVERT(`remoteId`, CHAR) AS `remote_id`,
+                `collectionId` AS `collection_id`
+            FROM `pimitemtable`
+            WHERE `mimeTypeId` = 2
+            AND `id` >= ",
+        );
+        query_builder.push_bind(args.min_id);
+        selection::push_selection(&mut query_builder, args, chunk, folder_ids.as_deref());
+        query_builder.push(" AND (");
 
-    // Add remote IDs from mail_list to the query
-    let mail_list = new_mails::find_new_mail_files(pool.clone(), args).await?;
-    if !mail_list.is_empty() {
-        query_builder.push(" OR `remoteId` IN (");
-        let mut separated = query_builder.separated(", ");
-        for mail in &mail_list {
-            separated.push_bind(mail);
+        let mut criteria = 0;
+        for criterion in Criterion::ALL {
+            let collection_ids = selected(criterion);
+            if collection_ids.as_ref().is_some_and(|ids| ids.is_empty())
+                || (criterion == Criterion::New && mail_list.is_empty())
+            {
+                continue;
+            }
+            if criteria > 0 {
+                query_builder.push(" OR ");
+            }
+            criteria += 1;
+            match criterion {
+                // Items kept in the Akonadi cache
+                Criterion::Dirty => query_builder.push("(`dirty` = 1"),
+                // Mails moved to a local folder are expected to be read
+                Criterion::Unseen => query_builder.push("(`remoteId` NOT LIKE '%:2,%S'"),
+                // Items flagged as `\ANSWERED` but not marked as replied
+                // These items also need to be processed and the flag
+                // changed to replied after moving.
+                Criterion::Answered => query_builder.push(
+                    "(`id` IN (SELECT pimItem_Id
+                     FROM `pimitemflagrelation`
+                     WHERE `flag_Id` IN (SELECT `id`
+                                         FROM `flagtable`
+                                         WHERE `name` LIKE '%ANSWERED'))
+                    AND `remoteId` NOT LIKE '%:2%RS'",
+                ),
+                // Add remote IDs of files with info suffix in new directories
+                Criterion::New => {
+                    query_builder.push("(`remoteId` IN (");
+                    let mut separated = query_builder.separated(", ");
+                    for mail in &mail_list {
+                        separated.push_bind(mail);
+                    }
+                    query_builder.push(")")
+                }
+            };
+            if let Some(ids) = collection_ids {
+                query_builder.push(" AND `collectionId` IN (");
+                let mut separated = query_builder.separated(", ");
+                for id in ids {
+                    separated.push_bind(id);
+                }
+                query_builder.push(")");
+            }
+            query_builder.push(")");
+        }
+        if criteria == 0 {
+            return Ok(Vec::new());
         }
-        query_builder.push(")");
-    }
 
-    // Close the main WHERE clause selecting only mails in local folders
-    query_builder.push(
-        ")
-        AND `collectionId` IN (
-            SELECT id FROM `collectiontable` WHERE `resourceId` = 3
-        )",
-    );
+        // Close the main WHERE clause selecting only mails in local folders
+        query_builder.push(
+            ")
+            AND `collectionId` IN (
+                SELECT id FROM `collectiontable` WHERE `resourceId` = 3
+            )",
+        );
+
+        // Add limit if specified in args, with a remote ID filter only after filtering
+        if args.limit > 0 {
+            query_builder.push(
+                "
+            ORDER BY `id`",
+            );
+            if remote_id_regex.is_none() {
+                query_builder.push(format!(
+                    "
+            LIMIT {}",
+                    args.limit
+                ));
+            }
+        }
 
-    // Add limit if specified in args
+        query.extend(
+            query_builder
+                .build_query_as::<TodoPimItem>()
+                .fetch_all(&pool)
+                .await?,
+        );
+    }
+    query.sort_by_key(|item| item.id);
+    if let Some(regex) = remote_id_regex {
+        query.retain(|item| {
+            item.remote_id
+                .as_ref()
+                .is_some_and(|rid| regex.is_match(rid))
+        });
+    }
     if args.limit > 0 {
-        query_builder.push(format!(
-            "
-        ORDER BY `id`
-        LIMIT {}",
-            args.limit
-        ));
+        query.truncate(args.limit);
     }
-
-    let query = query_builder.build_query_as::<_>().fetch_all(&pool).await?;
     Ok(query)
 }
+
+/// Fetch the ids of all pim items in a collection with the given remote ID.
+pub async fn fetch_items_by_remote_id(
+    pool: Pool<MySql>,
+    collection_id: i64,
+    remote_id: &str,
+) -> Result<Vec<i64>> {
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT `id` FROM `pimitemtable`
+         WHERE `collectionId` = ? AND CONVERT(`remoteId`, CHAR) = ?
+         ORDER BY `id`",
+    )
+    .bind(collection_id)
+    .bind(remote_id)
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Fetch the remote IDs of all mails in local folders, grouped by collection.
+pub async fn fetch_remote_ids_by_collection(
+    pool: Pool<MySql>,
+) -> Result<HashMap<i64, HashSet<String>>> {
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT `collectionId`, CONVERT(`remoteId`, CHAR)
+         FROM `pimitemtable`
+         WHERE `mimeTypeId` = 2
+         AND `remoteId` IS NOT NULL
+         AND `collectionId` IN (
+             SELECT id FROM `collectiontable` WHERE `resourceId` = 3
+         )",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let mut remote_ids: HashMap<i64, HashSet<String>> = HashMap::new();
+    for (collection_id, remote_id) in rows {
+        remote_ids
+            .entry(collection_id)
+            .or_default()
+            .insert(remote_id);
+    }
+    Ok(remote_ids)
+}
+
+/// Fetch all pim items in a collection whose gid carries the given
+/// Message-ID, with or without angle brackets.
+pub async fn fetch_items_by_gid(
+    pool: Pool<MySql>,
+    collection_id: i64,
+    message_id: &str,
+) -> Result<Vec<TodoPimItem>> {
+    let items = sqlx::query_as::<_, TodoPimItem>(
+        "SELECT `id`,
+            CONVERT(`remoteId`, CHAR) AS `remote_id`,
+            `collectionId` AS `collection_id`
+        FROM `pimitemtable`
+        WHERE `collectionId` = ? AND CONVERT(`gid`, CHAR) IN (?, ?)
+        ORDER BY `id`",
+    )
+    .bind(collection_id)
+    .bind(message_id)
+    .bind(format!("<{}>", message_id))
+    .fetch_all(&pool)
+    .await?;
+    Ok(items)
+}
+
+/// Fetch the `datetime` of a pim item in seconds since UNIX_EPOCH.
+pub async fn fetch_item_datetime(pool: Pool<MySql>, id: i64) -> Result<Option<u64>> {
+    let row: Option<(Option<i64>,)> = sqlx::query_as(
+        "SELECT CAST(UNIX_TIMESTAMP(`datetime`) AS SIGNED) FROM `pimitemtable` WHERE `id` = ?",
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await?;
+    Ok(row
+        .and_then(|(time_stamp,)| time_stamp)
+        .and_then(|time_stamp| u64::try_from(time_stamp).ok()))
+}
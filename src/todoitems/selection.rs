@@ -0,0 +1,216 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cmdline::CliArgs;
+use crate::context::RunContext;
+use crate::rules::fetch_collection_paths;
+use anyhow::Result;
+use regex::Regex;
+use sqlx::{MySql, Pool, QueryBuilder};
+use std::io::Read;
+use std::sync::LazyLock;
+
+/// Date as accepted by `parse_date`.
+static DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}( \d{2}:\d{2}(:\d{2})?)?$").unwrap());
+
+/// Check a date given as `YYYY-MM-DD`, optionally followed by ` HH:MM:SS`
+/// or ` HH:MM`, as MySQL compares it with `pimitemtable.datetime`.
+pub fn parse_date(value: &str) -> std::result::Result<String, String> {
+    let value = value.trim();
+    if !DATE.is_match(value) {
+        return Err(format!(
+            "invalid date `{}`, expected YYYY-MM-DD[ HH:MM:SS]",
+            value
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// Check that a regular expression is valid.
+pub fn parse_regex(value: &str) -> std::result::Result<String, String> {
+    Regex::new(value).map_err(|e| e.to_string())?;
+    Ok(value.to_string())
+}
+
+/// Parse message IDs separated by white space or commas; `#` starts a
+/// comment.
+pub fn parse_item_ids(text: &str) -> Result<Vec<i64>> {
+    let mut ids = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        for word in line.split(|c: char| c.is_whitespace() || c == ',') {
+            if word.is_empty() {
+                continue;
+            }
+            ids.push(word.parse().map_err(|_| {
+                anyhow::anyhow!("Line {}: invalid message ID `{}`", number + 1, word)
+            })?);
+        }
+    }
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+/// Read the message IDs given by `--ids-from` from a file or from stdin.
+///
+/// # Arguments
+/// - `args`: Command line arguments
+///
+/// Returns `Result<Option<Vec<i64>>>`, `None` without `--ids-from`
+///
+pub fn load_item_ids(args: &CliArgs) -> Result<Option<Vec<i64>>> {
+    let Some(path) = args.ids_from.as_ref() else {
+        return Ok(None);
+    };
+    let text = if path == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read message IDs: {}: {}", path, e))?
+    };
+    Ok(Some(parse_item_ids(&text)?))
+}
+
+/// Fetch the ids of a local folder, given by its path below the root such
+/// as `inbox/lists`, and of all its subfolders.
+///
+/// # Arguments
+/// - `pool`: Database connection pool
+/// - `folder`: Collection path, an empty path for the root
+///
+/// Returns `Result<Vec<i64>>`
+///
+pub async fn fetch_subtree_collections(pool: Pool<MySql>, folder: &str) -> Result<Vec<i64>> {
+    let folder = folder.trim_matches('/');
+    let mut ids: Vec<i64> = fetch_collection_paths(pool)
+        .await?
+        .into_iter()
+        .filter(|(_, (path, _))| {
+            folder.is_empty()
+                || path == folder
+                || path
+                    .strip_prefix(folder)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .map(|(id, _)| id)
+        .collect();
+    if ids.is_empty() {
+        anyhow::bail!("Folder {} not found", folder);
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+/// Describe the selection options in effect for the dry-run header.
+///
+/// # Arguments
+/// - `args`: Command line arguments
+/// - `ctx`: Message IDs of `--ids-from`
+///
+/// Returns `None` if all todo items are selected
+///
+pub fn describe_selection(args: &CliArgs, ctx: &RunContext) -> Option<String> {
+    let mut parts = Vec::new();
+    match (args.min_id, args.max_id) {
+        (0, 0) => {}
+        (min_id, 0) => parts.push(format!("message IDs from {}", min_id)),
+        (0, max_id) => parts.push(format!("message IDs up to {}", max_id)),
+        (min_id, max_id) => parts.push(format!("message IDs {} to {}", min_id, max_id)),
+    }
+    if let Some(ids) = &ctx.item_ids {
+        parts.push(format!(
+            "{} message IDs from {}",
+            ids.len(),
+            args.ids_from.as_deref().unwrap_or_default()
+        ));
+    }
+    if let Some(folder) = &args.folder {
+        parts.push(format!("folder {} and its subfolders", folder));
+    }
+    if let Some(since) = &args.since {
+        parts.push(format!("time stamps from {}", since));
+    }
+    if let Some(until) = &args.until {
+        parts.push(format!("time stamps up to {}", until));
+    }
+    if let Some(regex) = &args.remote_id_regex {
+        parts.push(format!("remote IDs matching {}", regex));
+    }
+    if args.ignore_new_dirs {
+        parts.push("ignoring new directories".to_string());
+    }
+    if args.limit > 0 {
+        parts.push(format!("at most {} mails", args.limit));
+    }
+    if parts.is_empty() {
+        None
+    } else {
+        Some(format!("Selection: {}.", parts.join(", ")))
+    }
+}
+
+/// Add the selection options but `--remote-id-regex` to the `WHERE` clause
+/// of a query on the `pimitemtable`.
+///
+/// # Arguments
+/// - `query_builder`: Query with an open `WHERE` clause
+/// - `args`: Command line arguments
+/// - `item_ids`: Message IDs of `--ids-from`, if given
+/// - `folder_ids`: Collections of `--folder`, if given
+///
+pub fn push_selection(
+    query_builder: &mut QueryBuilder<'_, MySql>,
+    args: &CliArgs,
+    item_ids: Option<&[i64]>,
+    folder_ids: Option<&[i64]>,
+) {
+    if args.max_id > 0 {
+        query_builder.push(" AND `id` <= ");
+        query_builder.push_bind(args.max_id);
+    }
+    if let Some(since) = &args.since {
+        query_builder.push(" AND `datetime` >= ");
+        query_builder.push_bind(since.clone());
+    }
+    if let Some(until) = &args.until {
+        // A date without time includes the whole day
+        if until.contains(' ') {
+            query_builder.push(" AND `datetime` <= ");
+            query_builder.push_bind(until.clone());
+        } else {
+            query_builder.push(" AND `datetime` < DATE_ADD(");
+            query_builder.push_bind(until.clone());
+            query_builder.push(", INTERVAL 1 DAY)");
+        }
+    }
+    if let Some(ids) = item_ids {
+        push_id_list(query_builder, "id", ids);
+    }
+    if let Some(ids) = folder_ids {
+        push_id_list(query_builder, "collectionId", ids);
+    }
+}
+
+fn push_id_list(query_builder: &mut QueryBuilder<'_, MySql>, column: &str, ids: &[i64]) {
+    query_builder.push(format!(" AND `{}` IN (", column));
+    let mut separated = query_builder.separated(", ");
+    for id in ids {
+        separated.push_bind(*id);
+    }
+    query_builder.push(")");
+}
@@ -29,6 +29,7 @@
 ///
 mod tests {
     use crate::cmdline::CliArgs;
+    use crate::context::RunContext;
     use crate::todoitems::fetch_todo_pim_items;
     use anyhow::Result;
     use sqlx::mysql::MySqlPool;
@@ -48,7 +49,7 @@ mod tests {
             ignore_new_dirs: true,
             ..Default::default()
         };
-        let result = fetch_todo_pim_items(pool.clone(), &args).await;
+        let result = fetch_todo_pim_items(pool.clone(), &args, &RunContext::default()).await;
         assert!(
             result.is_err(),
             "Expected an error due to missing database tables."
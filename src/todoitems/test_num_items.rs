@@ -45,6 +45,7 @@
 mod tests {
 
     use crate::cmdline::CliArgs;
+    use crate::context::RunContext;
     use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
     use crate::todoitems::fetch_todo_pim_items;
     use anyhow::Result;
@@ -59,7 +60,7 @@ mod tests {
         let args = create_test_cli_args(&temp_dir, false);
 
         // Fetch todo items from the database
-        let todo_items = fetch_todo_pim_items(pool.clone(), &args).await?;
+        let todo_items = fetch_todo_pim_items(pool.clone(), &args, &RunContext::default()).await?;
 
         // Verify the number of todo items fetched
         assert_eq!(todo_items.len(), 9);
@@ -85,7 +86,7 @@ mod tests {
         };
 
         // Fetch todo items from the database ignoring new directories
-        let todo_items = fetch_todo_pim_items(pool.clone(), &args).await?;
+        let todo_items = fetch_todo_pim_items(pool.clone(), &args, &RunContext::default()).await?;
 
         // Verify the number of todo items fetched
         assert_eq!(todo_items.len(), 6);
@@ -111,7 +112,7 @@ mod tests {
         };
 
         // Fetch todo items from the database
-        let todo_items = fetch_todo_pim_items(pool.clone(), &args).await?;
+        let todo_items = fetch_todo_pim_items(pool.clone(), &args, &RunContext::default()).await?;
 
         // Verify the number of todo items is below the given limit
         assert_eq!(todo_items.len(), 5);
@@ -137,7 +138,7 @@ mod tests {
         };
 
         // Fetch todo items from the database ignoring new directories
-        let todo_items = fetch_todo_pim_items(pool.clone(), &args).await?;
+        let todo_items = fetch_todo_pim_items(pool.clone(), &args, &RunContext::default()).await?;
 
         // Verify the number of todo items fetched
         assert_eq!(todo_items.len(), 4);
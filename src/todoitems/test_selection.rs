@@ -0,0 +1,188 @@
+// Copyright 2026 fix_local_mail C. Pospiech
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+/// Test module for the selection options of the todo items.
+///
+/// # Test Setup
+///
+/// The database tests create a temporary mail directory structure by copying test
+/// data from `tests/data` to a unique temporary location. Without selection
+/// options, the fixture yields 9 todo items: items 50628, 50638, 50642, 50643
+/// and 50645 in `inbox/nirwana`, items 206 and 50377 in `inbox/temporary`,
+/// item 1207 in `inbox/to_be_filed`, all with time stamps of 2025-12-30, and
+/// item 132632 in the root collection from 2026-01-29.
+///
+/// # Test Cases
+///
+/// - `test_parse_date`: Verifies accepted and rejected dates.
+/// - `test_parse_item_ids`: Verifies separators, comments and errors.
+/// - `test_describe_selection`: Verifies the dry-run header.
+/// - `test_select_folder_subtree`: Verifies `--folder` with and without subfolders.
+/// - `test_select_id_window_and_list`: Verifies `--max-id` and `--ids-from`, also with
+///   more message IDs than a single query binds.
+/// - `test_select_date_range_and_regex`: Verifies `--since`, `--until` and
+///   `--remote-id-regex` together with `--limit`.
+///
+mod tests {
+    use crate::cmdline::CliArgs;
+    use crate::context::RunContext;
+    use crate::mockup::{create_test_cli_args, setup_tmp_mail_dir, teardown_tmp_mail_dir};
+    use crate::todoitems::fetch_todo_pim_items;
+    use crate::todoitems::selection::{
+        describe_selection, load_item_ids, parse_date, parse_item_ids, parse_regex,
+    };
+    use anyhow::Result;
+    use sqlx::{MySql, Pool};
+
+    async fn fetch_ids(pool: Pool<MySql>, args: &CliArgs) -> Result<Vec<i64>> {
+        let ctx = RunContext {
+            item_ids: load_item_ids(args)?,
+            ..RunContext::default()
+        };
+        let mut ids: Vec<i64> = fetch_todo_pim_items(pool, args, &ctx)
+            .await?
+            .iter()
+            .map(|item| item.id)
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2025-12-30"), Ok("2025-12-30".to_string()));
+        assert_eq!(
+            parse_date(" 2025-12-30 15:19 "),
+            Ok("2025-12-30 15:19".to_string())
+        );
+        assert!(parse_date("2025-12-30 15:19:02").is_ok());
+        assert!(parse_date("30.12.2025").is_err());
+        assert!(parse_date("2025-12-30 15").is_err());
+        assert!(parse_regex(r"\.helios").is_ok());
+        assert!(parse_regex("helios(").is_err());
+    }
+
+    #[test]
+    fn test_parse_item_ids() -> Result<()> {
+        assert_eq!(
+            parse_item_ids("50628, 206\n# comment\n1207 206 # again\n")?,
+            vec![206, 1207, 50628]
+        );
+        assert!(parse_item_ids("206\n12a\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_selection() {
+        let ctx = RunContext::default();
+        assert_eq!(describe_selection(&CliArgs::default(), &ctx), None);
+        let args = CliArgs {
+            min_id: 200,
+            max_id: 50000,
+            folder: Some("inbox".to_string()),
+            until: Some("2025-12-31".to_string()),
+            limit: 5,
+            ..CliArgs::default()
+        };
+        assert_eq!(
+            describe_selection(&args, &ctx).as_deref(),
+            Some(
+                "Selection: message IDs 200 to 50000, folder inbox and its subfolders, \
+                 time stamps up to 2025-12-31, at most 5 mails."
+            )
+        );
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_select_folder_subtree(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+
+        args.folder = Some("inbox/temporary".to_string());
+        assert_eq!(fetch_ids(pool.clone(), &args).await?, vec![206, 50377]);
+
+        args.folder = Some("inbox/".to_string());
+        assert_eq!(fetch_ids(pool.clone(), &args).await?.len(), 8);
+
+        args.folder = Some("inbox/missing".to_string());
+        assert!(
+            fetch_todo_pim_items(pool.clone(), &args, &RunContext::default())
+                .await
+                .is_err()
+        );
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_select_id_window_and_list(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+
+        args.min_id = 1000;
+        args.max_id = 50500;
+        assert_eq!(fetch_ids(pool.clone(), &args).await?, vec![1207, 50377]);
+
+        args.min_id = 0;
+        args.max_id = 0;
+        args.ids_from = Some(format!("{}/ids.txt", temp_dir));
+        std::fs::write(
+            args.ids_from.as_ref().unwrap(),
+            "206\n1322 # not a todo item\n",
+        )?;
+        assert_eq!(fetch_ids(pool.clone(), &args).await?, vec![206]);
+
+        // Long lists are queried in chunks
+        let ids: Vec<String> = (1..=30000).map(|id| id.to_string()).collect();
+        std::fs::write(args.ids_from.as_ref().unwrap(), ids.join("\n"))?;
+        assert_eq!(fetch_ids(pool.clone(), &args).await?, vec![206, 1207]);
+
+        std::fs::write(args.ids_from.as_ref().unwrap(), "# none\n")?;
+        assert!(fetch_ids(pool.clone(), &args).await?.is_empty());
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("../../tests/fixtures/akonadi.sql"))]
+    async fn test_select_date_range_and_regex(pool: Pool<MySql>) -> Result<()> {
+        let temp_dir: String = setup_tmp_mail_dir()?;
+        let mut args = create_test_cli_args(&temp_dir, false);
+
+        args.until = Some("2025-12-30".to_string());
+        assert_eq!(fetch_ids(pool.clone(), &args).await?.len(), 8);
+
+        args.since = Some("2025-12-30 15:19".to_string());
+        assert_eq!(
+            fetch_ids(pool.clone(), &args).await?,
+            vec![50628, 50638, 50642, 50643, 50645]
+        );
+
+        args.since = None;
+        args.until = None;
+        args.remote_id_regex = Some(r"\.helios".to_string());
+        assert_eq!(
+            fetch_ids(pool.clone(), &args).await?,
+            vec![1207, 50377, 50628]
+        );
+        // The limit applies to the matching items
+        args.limit = 2;
+        assert_eq!(fetch_ids(pool.clone(), &args).await?, vec![1207, 50377]);
+
+        teardown_tmp_mail_dir(&temp_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+--
+-- Add duplicate `pimitemtable` rows for the mails in tests/data
+--
+-- 50700 duplicates item 1322 by `(collectionId, remoteId)` and carries the \FLAGGED flag.
+-- 50701 duplicates item 1207 by `gid`, but no file backs its remote ID.
+--
+
+UPDATE `pimitemtable` SET `gid` = '8527087.7EAinbc0l4@helios' WHERE `id` = 1207;
+
+INSERT INTO `pimitemtable` VALUES
+(50700,0,'1330783242.R2038.sirius:2,S',NULL,NULL,66,2,'2025-12-30 15:20:00','2025-12-30 15:20:00',0,39110),
+(50701,0,'1491255228.R999.helios',NULL,'8527087.7EAinbc0l4@helios',66,2,'2025-12-30 15:20:00','2025-12-30 15:20:00',0,1600);
+
+INSERT INTO `pimitemflagrelation` VALUES
+(50700,14);
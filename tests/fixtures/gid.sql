@@ -0,0 +1,10 @@
+--
+-- Set `pimitemtable`.`gid` (the Message-ID) for the mails in tests/data
+--
+
+UPDATE `pimitemtable` SET `gid` = '<KL1PR03MB7765F01F4FF98062ADEA08E5F951A@KL1PR03MB7765.apcprd03.prod.outlook.com>' WHERE `id` = 206;
+UPDATE `pimitemtable` SET `gid` = '8527087.7EAinbc0l4@helios' WHERE `id` = 1207;
+UPDATE `pimitemtable` SET `gid` = 'OFDCFBA1D1.99586397-ONC125782D.0034BE63-C125782D.0034DFDB@LocalDomain' WHERE `id` = 1322;
+UPDATE `pimitemtable` SET `gid` = '2812264.mvXUDI8C0e@helios' WHERE `id` = 50377;
+UPDATE `pimitemtable` SET `gid` = '0107019b65edb488-7a4843d8-ec2b-4dea-96c0-368706c1bc0a-000000@eu-central-1.amazonses.com' WHERE `id` = 50628;
+UPDATE `pimitemtable` SET `gid` = 'NM612562E06004ECB8Ctappt_mid_prod3@mkt.flytap.com' WHERE `id` = 50643;